@@ -1,12 +1,13 @@
 use crate::{
     automaton::Automaton::*,
-    dfa::DFA,
+    dfa::{ToDfa, DFA},
     nfa::{ToNfa, NFA},
     regex::Regex,
 };
 use std::{
+    borrow::Cow,
     cmp::{Ordering, Ordering::*, PartialEq, PartialOrd},
-    fmt::{Debug, Display},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     hash::Hash,
     ops::RangeBounds,
 };
@@ -36,6 +37,8 @@ pub enum Automaton<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
 
 pub trait Buildable<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     /// Returns the automaton that accepts a word if and only if it is accepted by `self` or by `other`.
+    ///
+    /// The alphabet of the result is the union of their alphabets.
     fn unite(self, other: Self) -> Self;
     /// Returns the automaton that accepts a word if and only if it is the concatenation of a word accepted by `self` and of a word accepted by `other`.
     fn concatenate(self, other: Self) -> Self;
@@ -91,7 +94,7 @@ pub trait Automata<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     /// Returns `true` if and only if `self` is [`full`](./trait.Automata.html#full-automaton).
     fn is_full(&self) -> bool;
 
-    /// Returns an automaton that accepts the same words as `self` but is [`complete`](./trait.Automata.html#complete-automaton).
+    /// Returns an automaton that accepts the same words as `self` but is [`complete`](./trait.Automata.html#complete-automaton) over its alphabet.
     fn complete(self) -> Self;
     /// Returns an automaton that accepts the same words as `self` but is [`reachable`](./trait.Automata.html#reachable-automaton).
     fn make_reachable(self) -> Self;
@@ -99,7 +102,9 @@ pub trait Automata<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     fn make_coreachable(self) -> Self;
     /// Returns an automaton that accepts the same words as `self` but is [`trimmed`](./trait.Automata.html#trimmed-automaton).
     fn trim(self) -> Self;
-    /// Returns an automaton that accepts a word if and only if `self` doesn't accept this word.
+    /// Returns an automaton that accepts a word over the alphabet of `self` if and only if `self` doesn't accept this word.
+    ///
+    /// The binary operations involving a complement (e.g. `contains` or `-`) first extend both alphabets to their union.
     fn negate(self) -> Self;
     /// Returns an automaton that accepts a word if and only if `self` accepts the reversed word.
     fn reverse(self) -> Self;
@@ -111,23 +116,69 @@ pub enum FromRawError<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     InvalidInitial(usize),
     InvalidFinal(usize),
     InvalidTransition(usize, V, usize),
+    /// An epsilon transition from the first state to the second one, which doesn't exist.
+    InvalidEpsilonTransition(usize, usize),
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Display for FromRawError<V> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            FromRawError::UnknownLetter(v) => write!(f, "the letter {} is not in the alphabet", v),
+            FromRawError::InvalidInitial(s) => write!(f, "the initial state {} doesn't exist", s),
+            FromRawError::InvalidFinal(s) => write!(f, "the final state {} doesn't exist", s),
+            FromRawError::InvalidTransition(s, v, t) => write!(
+                f,
+                "the transition from {} reading {} leads to {}, which doesn't exist",
+                s, v, t
+            ),
+            FromRawError::InvalidEpsilonTransition(s, t) => write!(
+                f,
+                "the epsilon transition from {} leads to {}, which doesn't exist",
+                s, t
+            ),
+        }
+    }
 }
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automaton<V> {
     /// A contains B if and only if for each `word` w, if B `accepts` w then A `accepts` w.
+    ///
+    /// Both automata are determinized (unless they already are DFAs), then a word accepted by B and rejected by A is searched in their product.
     pub fn contains(&self, other: &Automaton<V>) -> bool {
-        let a = match self {
+        let a = self.as_dfa();
+        let b = other.as_dfa();
+        a.product_shortest_word(&b, (a.initial, b.initial), |x, y| !x && y)
+            .is_none()
+    }
+
+    /// Returns an automaton that accepts a word if and only if this word is accepted by both `self` and `other`.
+    ///
+    /// The result is a DFA if one of them is a DFA, and a NFA otherwise.
+    pub fn intersect(&self, other: &Automaton<V>) -> Automaton<V> {
+        match (self, other) {
+            (DFA(_), _) | (_, DFA(_)) => DFA(self
+                .as_dfa()
+                .into_owned()
+                .intersect(other.as_dfa().into_owned())),
+            _ => NFA(self.to_nfa().intersect(other.to_nfa())),
+        }
+    }
+
+    // borrows the automaton if it is a DFA, and determinizes it otherwise
+    fn as_dfa(&self) -> Cow<'_, DFA<V>> {
+        match self {
+            DFA(a) => Cow::Borrowed(a),
+            NFA(a) => Cow::Owned(a.to_dfa()),
+            REG(a) => Cow::Owned(a.to_dfa()),
+        }
+    }
+
+    fn to_nfa(&self) -> NFA<V> {
+        match self {
             DFA(a) => a.to_nfa(),
-            NFA(a) => a.to_nfa(),
+            NFA(a) => a.clone(),
             REG(a) => a.to_nfa(),
-        };
-        let b = match other {
-            DFA(b) => b.to_nfa(),
-            NFA(b) => b.to_nfa(),
-            REG(b) => b.to_nfa(),
-        };
-
-        a.contains(&b)
+        }
     }
 }
 
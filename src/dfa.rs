@@ -1,19 +1,40 @@
 use crate::{
     automaton::{Automata, Automaton, Buildable, FromRawError},
     nfa::{ToNfa, NFA},
-    regex::{Regex, ToRegex},
+    regex::{Operations, ParseError, Regex, ToRegex},
+    utils::{reachable_product, share_alphabets},
 };
 use std::{
-    cmp::{Ordering, Ordering::*, PartialEq, PartialOrd},
-    collections::{HashMap, HashSet},
-    fmt::{Debug, Display},
-    hash::Hash,
+    cmp::{Ordering, Ordering::*, PartialEq, PartialOrd, Reverse},
+    collections::{hash_map::Entry, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+    io::{self, Write},
     ops::{Add, Mul, Neg, Not, RangeBounds, Sub},
     str::FromStr,
 };
 
+/// The maximal size of the alphabet for [`DFA::canonical_up_to_relabeling`](./struct.DFA.html#method.canonical_up_to_relabeling).
+pub const MAX_RELABELING_ALPHABET: usize = 8;
+
+/// The weight of the nodes of the graph returned by [`DFA::to_petgraph`](./struct.DFA.html#method.to_petgraph).
+#[cfg(feature = "petgraph")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateInfo {
+    pub is_initial: bool,
+    pub is_final: bool,
+}
+
+/// The number of words of a language, returned by [`DFA::cardinality`](./struct.DFA.html#method.cardinality).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cardinality {
+    Finite(u128),
+    Infinite,
+}
+
 /// <https://en.wikipedia.org/wiki/Deterministic_finite_automaton>
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DFA<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     pub(crate) alphabet: HashSet<V>,
     pub(crate) initial: usize,
@@ -27,25 +48,1471 @@ pub trait ToDfa<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
 }
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> DFA<V> {
+    /// Returns the alphabet.
+    pub fn alphabet(&self) -> &HashSet<V> {
+        &self.alphabet
+    }
+
+    /// Returns the number of states, which are numbered from `0`.
+    pub fn num_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the initial state.
+    pub fn initial(&self) -> usize {
+        self.initial
+    }
+
+    /// Returns the final states.
+    pub fn finals(&self) -> &HashSet<usize> {
+        &self.finals
+    }
+
+    /// Returns the transitions from `state`, each letter being mapped to its target.
+    ///
+    /// # Panics
+    /// Panics if `state` doesn't exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use rustomaton::dfa::DFA;
+    ///
+    /// let dfa = DFA::from_pattern("ab|b").unwrap().minimize();
+    /// assert_eq!(dfa.alphabet().len(), 2);
+    /// assert_eq!(dfa.num_states(), 3);
+    /// let transitions: usize = (0..dfa.num_states())
+    ///     .map(|s| dfa.transitions_from(s).len())
+    ///     .sum();
+    /// assert_eq!(transitions, 3);
+    /// assert_eq!(dfa.transitions_from(dfa.initial()).len(), 2);
+    /// assert_eq!(dfa.finals().len(), 1);
+    /// ```
+    pub fn transitions_from(&self, state: usize) -> &HashMap<V, usize> {
+        &self.transitions[state]
+    }
+
+    /// Returns a DFA accepting the words accepted by both `self` and `b`, built directly as their product.
+    ///
+    /// Only the pairs of states reading the letters of both alphabets are built, the alphabet of the result being the union of them:
+    /// a word with a letter which isn't in the alphabet of one of them is rejected.
     pub fn intersect(self, b: DFA<V>) -> DFA<V> {
-        self.negate().unite(b.negate()).negate()
+        self.product(&b, |x, y| x && y)
+    }
+
+    /// Adds the letters to the alphabet, without any transition: for instance, the complement is then taken over the larger alphabet.
+    pub fn extend_alphabet<I: IntoIterator<Item = V>>(&mut self, letters: I) {
+        self.alphabet.extend(letters);
+    }
+
+    /// Returns a DFA accepting the words of `self`, optionally preceded by a word of `prefix` (i.e. `(prefix)?self`), determinizing only once.
+    pub fn optional_prefix(self, prefix: DFA<V>) -> DFA<V> {
+        self.to_nfa().optional_prefix(prefix.to_nfa()).to_dfa()
+    }
+
+    /// Returns a DFA accepting the words of `self`, optionally followed by a word of `suffix` (i.e. `self(suffix)?`), determinizing only once.
+    pub fn optional_suffix(self, suffix: DFA<V>) -> DFA<V> {
+        self.to_nfa().optional_suffix(suffix.to_nfa()).to_dfa()
     }
 
     /// The algorithm used is <https://en.wikipedia.org/wiki/DFA_minimization#Brzozowski's_algorithm>.
     pub fn minimize(self) -> DFA<V> {
-        self.reverse().to_dfa().reverse().to_dfa()
+        self.reverse_nfa().to_dfa().reverse_nfa().to_dfa()
+    }
+
+    /// Returns the minimal automaton of the language of `self`, computed with Hopcroft's partition refinement in `O(n |Σ| log n)`.
+    ///
+    /// The unreachable states are removed and a dead state is added to complete the automaton before the refinement,
+    /// and the class of the dead state is removed afterwards (unless the language is empty), so the result is trimmed like the one of `minimize`.
+    /// <https://en.wikipedia.org/wiki/DFA_minimization#Hopcroft's_algorithm>
+    pub fn minimize_hopcroft(self) -> DFA<V> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut reachable: Vec<usize> = self.reachable_states().into_iter().collect();
+        reachable.sort_unstable();
+        let numbers: HashMap<usize, usize> =
+            reachable.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        // the states of `self` are renumbered, the last one being the dead state
+        let dead = reachable.len();
+        let n = dead + 1;
+        let delta: Vec<Vec<usize>> = reachable
+            .iter()
+            .map(|s| {
+                letters
+                    .iter()
+                    .map(|v| self.transitions[*s].get(v).map_or(dead, |t| numbers[t]))
+                    .collect()
+            })
+            .chain(std::iter::once(vec![dead; letters.len()]))
+            .collect();
+        let mut inverse = vec![vec![Vec::new(); n]; letters.len()];
+        for (s, targets) in delta.iter().enumerate() {
+            for (a, t) in targets.iter().enumerate() {
+                inverse[a][*t].push(s);
+            }
+        }
+
+        let (finals, others): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|s| s < &dead && self.finals.contains(&reachable[*s]));
+        let mut blocks: Vec<Vec<usize>> = vec![finals, others];
+        blocks.retain(|b| !b.is_empty());
+        let mut block_of = vec![0; n];
+        for (b, block) in blocks.iter().enumerate() {
+            for s in block {
+                block_of[*s] = b;
+            }
+        }
+        let mut waiting: Vec<usize> = (0..blocks.len()).collect();
+        let mut in_waiting = vec![true; blocks.len()];
+
+        while let Some(splitter) = waiting.pop() {
+            in_waiting[splitter] = false;
+            let splitter = blocks[splitter].clone();
+            for inv in &inverse {
+                // the predecessors of the splitter, grouped by block
+                let mut touched: HashMap<usize, HashSet<usize>> = HashMap::new();
+                for t in &splitter {
+                    for s in &inv[*t] {
+                        touched.entry(block_of[*s]).or_default().insert(*s);
+                    }
+                }
+
+                let mut touched: Vec<(usize, HashSet<usize>)> = touched.into_iter().collect();
+                touched.sort_unstable_by_key(|(b, _)| *b);
+                for (b, inside) in touched {
+                    if inside.len() == blocks[b].len() {
+                        continue;
+                    }
+
+                    let (kept, moved): (Vec<usize>, Vec<usize>) =
+                        blocks[b].iter().partition(|s| !inside.contains(s));
+                    let l = blocks.len();
+                    for s in &moved {
+                        block_of[*s] = l;
+                    }
+                    let smaller = if in_waiting[b] || moved.len() <= kept.len() {
+                        l
+                    } else {
+                        b
+                    };
+                    blocks[b] = kept;
+                    blocks.push(moved);
+                    in_waiting.push(false);
+                    if !in_waiting[smaller] {
+                        in_waiting[smaller] = true;
+                        waiting.push(smaller);
+                    }
+                }
+            }
+        }
+
+        // the class of the dead state is removed, unless it contains the initial state
+        let initial = numbers[&self.initial];
+        let removed = if block_of[initial] == block_of[dead] {
+            None
+        } else {
+            Some(block_of[dead])
+        };
+        let mut classes = HashMap::new();
+        for b in &block_of[..dead] {
+            if Some(*b) != removed {
+                let l = classes.len();
+                classes.entry(*b).or_insert(l);
+            }
+        }
+
+        let mut transitions = vec![HashMap::new(); classes.len()];
+        let mut finals = HashSet::new();
+        for s in 0..dead {
+            if let Some(c) = classes.get(&block_of[s]) {
+                if self.finals.contains(&reachable[s]) {
+                    finals.insert(*c);
+                }
+                for (a, t) in delta[s].iter().enumerate() {
+                    if let Some(d) = classes.get(&block_of[*t]) {
+                        transitions[*c].insert(letters[a], *d);
+                    }
+                }
+            }
+        }
+
+        DFA {
+            alphabet: self.alphabet,
+            initial: classes[&block_of[initial]],
+            finals,
+            transitions,
+        }
+    }
+
+    /// Returns, for each state, the number of its Nerode class: two states have the same number if and only if they accept the same words.
+    ///
+    /// The classes are computed by partition refinement and numbered in the order of their first state, without building the minimized automaton.
+    /// All the states from which no final state is reachable are in the same class, as the implicit dead state.
+    pub fn equivalence_partition(&self) -> Vec<usize> {
+        let coreachable = self.coreachable_states();
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let n = self.transitions.len();
+        let mut blocks: Vec<usize> = (0..n)
+            .map(|s| {
+                if !coreachable.contains(&s) {
+                    0
+                } else if self.finals.contains(&s) {
+                    1
+                } else {
+                    2
+                }
+            })
+            .collect();
+        let mut count = 0;
+        loop {
+            let mut signatures = HashMap::new();
+            let refined: Vec<usize> = (0..n)
+                .map(|s| {
+                    // the transitions towards dead states are the same as missing transitions
+                    let targets: Vec<Option<usize>> = if coreachable.contains(&s) {
+                        letters
+                            .iter()
+                            .map(|v| {
+                                self.transitions[s]
+                                    .get(v)
+                                    .filter(|t| coreachable.contains(t))
+                                    .map(|t| blocks[*t])
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    let l = signatures.len();
+                    *signatures.entry((blocks[s], targets)).or_insert(l)
+                })
+                .collect();
+            blocks = refined;
+            if signatures.len() == count {
+                break;
+            }
+            count = signatures.len();
+        }
+
+        blocks
+    }
+
+    /// Returns true if all the states of `self` are reachable and no two of them accept the same words.
+    ///
+    /// A single dead state is allowed, so both the complete and the partial minimal automata are minimal.
+    pub fn is_minimal(&self) -> bool {
+        if self.reachable_states().len() != self.transitions.len() {
+            return false;
+        }
+        let classes: HashSet<usize> = self.equivalence_partition().into_iter().collect();
+        classes.len() == self.transitions.len()
+    }
+
+    /// Returns true if `self` is a minimal automaton accepting the same language as `reference`.
+    pub fn is_minimal_of(&self, reference: &DFA<V>) -> bool {
+        self.is_minimal() && self == reference
+    }
+
+    /// Returns a NFA accepting the images by `f` of the words of `self`, the letters being mapped one by one.
+    ///
+    /// The result isn't deterministic in general, since two letters may have the same image.
+    pub fn map_alphabet<W, F>(self, f: F) -> NFA<W>
+    where
+        W: Eq + Hash + Display + Copy + Clone + Debug + Ord,
+        F: Fn(&V) -> W,
+    {
+        self.to_nfa().map_alphabet(f)
+    }
+
+    /// Returns whether renaming the letters of the words of `self` by `perm` leaves the language unchanged (e.g. whether a language is case-insensitive).
+    pub fn invariant_under<F: Fn(&V) -> V>(&self, perm: F) -> bool {
+        *self == self.clone().map_alphabet(perm).to_dfa()
+    }
+
+    /// Returns a NFA that accepts a word if and only if `self` accepts the reversed word, without determinizing it.
+    pub fn reverse_nfa(self) -> NFA<V> {
+        let DFA {
+            alphabet,
+            initial,
+            finals,
+            transitions,
+        } = self;
+
+        NFA {
+            alphabet,
+            initials: (initial..=initial).collect(),
+            finals,
+            transitions: transitions
+                .into_iter()
+                .map(|map| map.into_iter().map(|(k, v)| (k, vec![v])).collect())
+                .collect(),
+            epsilon: HashMap::new(),
+        }
+        .reverse()
     }
 
     /// A contains B if and only if for each `word` w, if B `accepts` w then A `accepts` w.
+    ///
+    /// The words are taken over the union of the alphabets: a word with a letter which isn't in the alphabet of A isn't accepted by A.
     pub fn contains(&self, b: &DFA<V>) -> bool {
         self.to_nfa().contains(&b.to_nfa())
     }
 
+    /// Returns an iterator over the accepted words in shortlex order (by length, then in the alphabet order).
+    ///
+    /// The iterator is infinite if the language is infinite: use `take` to get the first words.
+    pub fn words(&self) -> impl Iterator<Item = Vec<V>> + '_ {
+        let live = self.coreachable_states();
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        // a breadth-first search over the words leading to live states, expanded in the alphabet order
+        let mut queue = VecDeque::new();
+        if live.contains(&self.initial) {
+            queue.push_back((self.initial, Vec::new()));
+        }
+        std::iter::from_fn(move || {
+            while let Some((s, word)) = queue.pop_front() {
+                for v in &letters {
+                    if let Some(t) = self.transitions[s].get(v).filter(|t| live.contains(t)) {
+                        let mut next = word.clone();
+                        next.push(*v);
+                        queue.push_back((*t, next));
+                    }
+                }
+                if self.finals.contains(&s) {
+                    return Some(word);
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns `Ok(())` if `self` contains `b`, otherwise `Err(w)` where `w` is a shortest word accepted by `b` but not by `self`
+    /// (the smallest one in the alphabet order among them).
+    pub fn contains_with_witness(&self, b: &DFA<V>) -> Result<(), Vec<V>> {
+        match self.product_shortest_word(b, (self.initial, b.initial), |x, y| !x && y) {
+            Some(w) => Err(w),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `Ok(())` if no word is accepted by both `self` and `b`, otherwise `Err(w)` where `w` is a shortest word accepted by both.
+    pub fn disjoint_witness(&self, b: &DFA<V>) -> Result<(), Vec<V>> {
+        match self.common_shortest_word(b) {
+            Some(w) => Err(w),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the shortest word accepted by both `self` and `b` (the smallest one in the alphabet order among them), or `None` if there is no such word.
+    ///
+    /// The intersection is explored on the fly, without building the intersection automaton.
+    pub fn common_shortest_word(&self, b: &DFA<V>) -> Option<Vec<V>> {
+        self.product_shortest_word(b, (self.initial, b.initial), |x, y| x && y)
+    }
+
+    /// Returns the shortest word (the smallest one in the alphabet order among them) accepted by the automaton, or `None` if the language is empty.
+    pub fn shortest_word(&self) -> Option<Vec<V>> {
+        self.product_shortest_word(self, (self.initial, self.initial), |x, _| x)
+    }
+
+    /// Returns the minimal total cost of an accepted word, the cost of a word being the sum of the costs of its letters, with a word of this cost,
+    /// or `None` if the language is empty.
+    ///
+    /// The costs being nonnegative, this is Dijkstra's algorithm from the initial state to the closest final state.
+    /// With a cost of `1` for every letter, a shortest word is returned.
+    pub fn cheapest_word<F: Fn(&V) -> u64>(&self, cost: F) -> Option<(u64, Vec<V>)> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut distances = vec![None; self.transitions.len()];
+        let mut predecessors: Vec<Option<(usize, V)>> = vec![None; self.transitions.len()];
+        let mut heap = BinaryHeap::new();
+        distances[self.initial] = Some(0);
+        heap.push(Reverse((0, self.initial)));
+
+        while let Some(Reverse((d, s))) = heap.pop() {
+            if distances[s] != Some(d) {
+                continue;
+            }
+            if self.finals.contains(&s) {
+                let mut word = Vec::new();
+                let mut actual = s;
+                while let Some((previous, v)) = predecessors[actual] {
+                    word.push(v);
+                    actual = previous;
+                }
+                word.reverse();
+                return Some((d, word));
+            }
+
+            for v in &letters {
+                if let Some(t) = self.transitions[s].get(v) {
+                    let dt = d.saturating_add(cost(v));
+                    if distances[*t].map_or(true, |old| dt < old) {
+                        distances[*t] = Some(dt);
+                        predecessors[*t] = Some((s, *v));
+                        heap.push(Reverse((dt, *t)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the shortest word (the smallest one in the alphabet order among them) rejected by the automaton, or `None` if every word on the alphabet is accepted.
+    ///
+    /// The missing transitions lead to an implicit dead state, so the complement is never built.
+    pub fn shortest_rejected(&self) -> Option<Vec<V>> {
+        self.product_shortest_word(self, (self.initial, self.initial), |x, _| !x)
+    }
+
+    /// Returns the shortest word `w` such that running `w` from `s` ends in a final state while running it from `t` doesn't (or vice versa), or `None` if `s` and `t` are equivalent.
+    /// Among the shortest such words, the smallest one in the alphabet order is returned.
+    ///
+    /// # Panics
+    /// Panics if `s` or `t` doesn't exist.
+    pub fn distinguishing_word(&self, s: usize, t: usize) -> Option<Vec<V>> {
+        for state in &[s, t] {
+            assert!(
+                *state < self.transitions.len(),
+                "the state {} doesn't exist (there are {} states)",
+                state,
+                self.transitions.len()
+            );
+        }
+        self.product_shortest_word(self, (s, t), |x, y| x != y)
+    }
+
+    /// Breadth-first search over the synchronous product of `self` and `other` from the pair `start`, where `None` stands for the implicit dead state.
+    /// Returns the shortest word (the smallest one in the alphabet order among them) leading to a pair of states whose finality satisfies `accept`.
+    pub(crate) fn product_shortest_word<F: Fn(bool, bool) -> bool>(
+        &self,
+        other: &DFA<V>,
+        start: (usize, usize),
+        accept: F,
+    ) -> Option<Vec<V>> {
+        let mut alphabet: Vec<V> = self.alphabet.union(&other.alphabet).copied().collect();
+        alphabet.sort();
+
+        let is_final = |dfa: &DFA<V>, state: Option<usize>| match state {
+            Some(s) => dfa.finals.contains(&s),
+            None => false,
+        };
+        let step = |dfa: &DFA<V>, state: Option<usize>, v: &V| {
+            state.and_then(|s| dfa.transitions[s].get(v).copied())
+        };
+
+        let start = (Some(start.0), Some(start.1));
+        let mut predecessors = HashMap::new();
+        let mut queue = VecDeque::new();
+        predecessors.insert(start, None);
+        queue.push_back(start);
+
+        while let Some(pair) = queue.pop_front() {
+            if accept(is_final(self, pair.0), is_final(other, pair.1)) {
+                let mut word = Vec::new();
+                let mut actual = pair;
+                while let Some(&Some((previous, letter))) = predecessors.get(&actual) {
+                    word.push(letter);
+                    actual = previous;
+                }
+                word.reverse();
+                return Some(word);
+            }
+
+            for v in &alphabet {
+                let next = (step(self, pair.0, v), step(other, pair.1, v));
+                if let Entry::Vacant(entry) = predecessors.entry(next) {
+                    entry.insert(Some((pair, *v)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Builds the reachable part of the synchronous product of `self` and `other` over the union of their alphabets, where `None` stands for the implicit dead state.
+    /// A pair of states is final if their finality satisfies `accept`; the pair of dead states is left out,
+    /// as are the pairs with one dead state which can't become final whatever the other state.
+    pub(crate) fn product<F: Fn(bool, bool) -> bool>(&self, other: &DFA<V>, accept: F) -> DFA<V> {
+        let alphabet: HashSet<V> = self.alphabet.union(&other.alphabet).copied().collect();
+        let mut letters: Vec<V> = alphabet.iter().copied().collect();
+        letters.sort();
+
+        let is_final = |dfa: &DFA<V>, state: Option<usize>| match state {
+            Some(s) => dfa.finals.contains(&s),
+            None => false,
+        };
+        let step = |dfa: &DFA<V>, state: Option<usize>, v: &V| {
+            state.and_then(|s| dfa.transitions[s].get(v).copied())
+        };
+
+        let hopeless = |pair: (Option<usize>, Option<usize>)| match pair {
+            (None, None) => true,
+            (None, Some(_)) => !accept(false, false) && !accept(false, true),
+            (Some(_), None) => !accept(false, false) && !accept(true, false),
+            (Some(_), Some(_)) => false,
+        };
+
+        let start = (Some(self.initial), Some(other.initial));
+        let (pairs, transitions) = reachable_product(&letters, start, |pair, v| {
+            Some((step(self, pair.0, v), step(other, pair.1, v))).filter(|next| !hopeless(*next))
+        });
+        let finals = pairs
+            .iter()
+            .enumerate()
+            .filter(|(_, pair)| accept(is_final(self, pair.0), is_final(other, pair.1)))
+            .map(|(num, _)| num)
+            .collect();
+
+        DFA {
+            alphabet,
+            initial: 0,
+            finals,
+            transitions,
+        }
+    }
+
+    /// Returns a DFA accepting the words accepted by exactly one of `self` and `b`, built directly as their product.
+    pub fn symmetric_difference(self, b: DFA<V>) -> DFA<V> {
+        self.product(&b, |x, y| x != y)
+    }
+
+    /// Returns a DFA accepting the words `x` such that `w` followed by `x` is accepted by `self`, starting from the state reached by reading `w`.
+    ///
+    /// The language is empty if `w` can't be read.
+    pub fn left_quotient(&self, w: &[V]) -> DFA<V> {
+        let mut actual = self.initial;
+        for l in w {
+            match self.transitions[actual].get(l) {
+                Some(t) => actual = *t,
+                None => return DFA::new_empty(&self.alphabet),
+            }
+        }
+        let mut dfa = self.clone();
+        dfa.initial = actual;
+        dfa
+    }
+
+    /// Returns a DFA accepting the words `x` such that `x` followed by `w` is accepted by `self`: the final states are those from which reading `w` leads to a final state.
+    pub fn right_quotient(&self, w: &[V]) -> DFA<V> {
+        let mut dfa = self.clone();
+        dfa.finals = (0..self.transitions.len())
+            .filter(|s| {
+                w.iter()
+                    .try_fold(*s, |actual, l| self.transitions[actual].get(l).copied())
+                    .is_some_and(|actual| self.finals.contains(&actual))
+            })
+            .collect();
+        dfa
+    }
+
+    /// Returns `Err(i)` if the letter at index `i` of `v` is the first one which isn't in the alphabet, otherwise `Ok(b)` where `b` tells whether `v` is accepted.
+    ///
+    /// Unlike [`run`](../automaton/trait.Automata.html#tymethod.run), this distinguishes a malformed input from a rejected one.
+    pub fn run_total(&self, v: &[V]) -> Result<bool, usize> {
+        match v.iter().position(|l| !self.alphabet.contains(l)) {
+            Some(i) => Err(i),
+            None => Ok(self.run(v)),
+        }
+    }
+
+    /// Returns the pairs of a state and a letter without transition, ordered by state and then by letter: these are the transitions `complete` adds.
+    pub fn missing_transitions(&self) -> Vec<(usize, V)> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+        self.transitions
+            .iter()
+            .enumerate()
+            .flat_map(|(s, map)| {
+                letters
+                    .iter()
+                    .filter(move |v| !map.contains_key(v))
+                    .map(move |v| (s, *v))
+            })
+            .collect()
+    }
+
+    /// Returns whether the word made of the letters of `it` is accepted, consuming them one by one.
+    ///
+    /// The iteration stops as soon as a transition is missing, the following letters being left unconsumed.
+    pub fn run_iter<I: IntoIterator<Item = V>>(&self, it: I) -> bool {
+        let mut actual = self.initial;
+        for l in it {
+            match self.transitions[actual].get(&l) {
+                Some(t) => actual = *t,
+                None => return false,
+            }
+        }
+        self.finals.contains(&actual)
+    }
+
+    /// Returns whether the empty word is accepted, i.e. whether the initial state is final.
+    pub fn accepts_empty(&self) -> bool {
+        self.finals.contains(&self.initial)
+    }
+
+    /// Returns whether `v` is accepted, and the states visited while reading it, starting with the initial state.
+    ///
+    /// If a transition is missing, the run stops there and the states visited until then are returned.
+    pub fn run_trace(&self, v: &[V]) -> (bool, Vec<usize>) {
+        let mut actual = self.initial;
+        let mut trace = Vec::with_capacity(v.len() + 1);
+        trace.push(actual);
+        for l in v {
+            match self.transitions[actual].get(l) {
+                Some(t) => actual = *t,
+                None => return (false, trace),
+            }
+            trace.push(actual);
+        }
+        (self.finals.contains(&actual), trace)
+    }
+
+    /// Returns, for each prefix length from `0` to `input.len()`, whether the prefix of `input` of this length is accepted.
+    ///
+    /// Once a transition is missing, all the longer prefixes are rejected.
+    pub fn prefix_accepts(&self, input: &[V]) -> Vec<bool> {
+        let mut accepts = Vec::with_capacity(input.len() + 1);
+        let mut actual = Some(self.initial);
+        accepts.push(self.finals.contains(&self.initial));
+        for l in input {
+            actual = actual.and_then(|s| self.transitions[s].get(l).copied());
+            accepts.push(actual.is_some_and(|s| self.finals.contains(&s)));
+        }
+        accepts
+    }
+
+    /// Returns the length of the longest prefix of `input` accepted by `self`, or `None` if no prefix (not even the empty one) is accepted.
+    pub fn longest_match(&self, input: &[V]) -> Option<usize> {
+        let mut actual = self.initial;
+        let mut last = if self.finals.contains(&actual) {
+            Some(0)
+        } else {
+            None
+        };
+
+        for (i, l) in input.iter().enumerate() {
+            match self.transitions[actual].get(l) {
+                Some(t) => actual = *t,
+                None => break,
+            }
+            if self.finals.contains(&actual) {
+                last = Some(i + 1);
+            }
+        }
+
+        last
+    }
+
+    /// Returns a topological ordering of the states which are both reachable and coreachable, or `None` if there is a cycle among them (i.e. if the language is infinite).
+    pub fn topo_order(&self) -> Option<Vec<usize>> {
+        let live = self.live_states();
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut in_degree: HashMap<usize, usize> = live.iter().map(|s| (*s, 0)).collect();
+        for s in &live {
+            for t in self.transitions[*s].values() {
+                if let Some(d) = in_degree.get_mut(t) {
+                    *d += 1;
+                }
+            }
+        }
+
+        // the states are visited in a deterministic order: sorted, and then in the alphabet order
+        let mut order = Vec::with_capacity(live.len());
+        let mut stack: Vec<usize> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(s, _)| *s)
+            .collect();
+        stack.sort_unstable_by(|a, b| b.cmp(a));
+        while let Some(s) = stack.pop() {
+            order.push(s);
+            for v in &letters {
+                if let Some(d) = self.transitions[s].get(v).and_then(|t| in_degree.get_mut(t)) {
+                    *d -= 1;
+                    if *d == 0 {
+                        stack.push(self.transitions[s][v]);
+                    }
+                }
+            }
+        }
+
+        if order.len() == live.len() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the language of `self` is finite.
+    pub fn is_finite(&self) -> bool {
+        self.topo_order().is_some()
+    }
+
+    /// Returns a decomposition `(x, y, z)` of an accepted word with `y` nonempty such that `x·yⁿ·z` is accepted for every `n`,
+    /// or `None` if the language is finite.
+    ///
+    /// This is the constructive version of `is_finite`: `y` labels a cycle of live states, `x` is a shortest path to it and `z` a shortest path from it to a final state.
+    pub fn infinite_witness(&self) -> Option<(Vec<V>, Vec<V>, Vec<V>)> {
+        let live = self.live_states();
+        if !live.contains(&self.initial) {
+            return None;
+        }
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        // the live states in breadth-first order, with the shortest word leading to each of them
+        let mut paths: HashMap<usize, Vec<V>> = HashMap::new();
+        paths.insert(self.initial, Vec::new());
+        let mut order = vec![self.initial];
+        let mut i = 0;
+        while i < order.len() {
+            let s = order[i];
+            for v in &letters {
+                if let Some(t) = self.transitions[s].get(v) {
+                    if live.contains(t) && !paths.contains_key(t) {
+                        let mut path = paths[&s].clone();
+                        path.push(*v);
+                        paths.insert(*t, path);
+                        order.push(*t);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        for s in order {
+            let cycle = letters
+                .iter()
+                .filter_map(|v| {
+                    let t = self.transitions[s].get(v).filter(|t| live.contains(t))?;
+                    let mut cycle = vec![*v];
+                    cycle.extend(self.live_path(*t, &live, |u| u == s)?);
+                    Some(cycle)
+                })
+                .min_by_key(|cycle| cycle.len());
+            if let Some(y) = cycle {
+                let z = self.live_path(s, &live, |u| self.finals.contains(&u))?;
+                return Some((paths.remove(&s).unwrap(), y, z));
+            }
+        }
+
+        None
+    }
+
+    /// Returns the shortest word (the smallest one in the alphabet order among them) leading from `from` to a state satisfying `target` through the states of `live`.
+    fn live_path<F: Fn(usize) -> bool>(
+        &self,
+        from: usize,
+        live: &HashSet<usize>,
+        target: F,
+    ) -> Option<Vec<V>> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut predecessors: HashMap<usize, Option<(usize, V)>> = HashMap::new();
+        predecessors.insert(from, None);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(s) = queue.pop_front() {
+            if target(s) {
+                let mut word = Vec::new();
+                let mut actual = s;
+                while let Some((previous, v)) = predecessors[&actual] {
+                    word.push(v);
+                    actual = previous;
+                }
+                word.reverse();
+                return Some(word);
+            }
+            for v in &letters {
+                if let Some(t) = self.transitions[s].get(v) {
+                    if live.contains(t) && !predecessors.contains_key(t) {
+                        predecessors.insert(*t, Some((s, *v)));
+                        queue.push_back(*t);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns all the accepted words, sorted, or `None` if the language is infinite.
+    pub fn language(&self) -> Option<Vec<Vec<V>>> {
+        if !self.is_finite() {
+            return None;
+        }
+
+        // the live states are acyclic, so the enumeration ends
+        let mut words: Vec<Vec<V>> = self.words().collect();
+        words.sort();
+        Some(words)
+    }
+
+    /// Returns the number of accepted words, or `Cardinality::Infinite` if the language is infinite.
+    ///
+    /// # Panics
+    /// Panics if the number of accepted words doesn't fit in a `u128`
+    /// (the numbers of words of each length can then be computed with `count_words_big` and the `num-bigint` feature).
+    pub fn cardinality(&self) -> Cardinality {
+        match self.topo_order() {
+            // an accepted word visits each live state at most once
+            Some(order) => Cardinality::Finite(
+                self.growth_with(order.len(), u128::checked_add)
+                    .and_then(|growth| growth.into_iter().try_fold(0u128, u128::checked_add))
+                    .expect("the number of accepted words overflows a u128, see count_words_big"),
+            ),
+            None => Cardinality::Infinite,
+        }
+    }
+
+    /// Returns a longest accepted word, or `Ok(None)` if the language is empty, or an error if the language is infinite.
+    pub fn longest_word(&self) -> Result<Option<Vec<V>>, String> {
+        let order = self
+            .topo_order()
+            .ok_or_else(|| "the language is infinite".to_string())?;
+        if order.is_empty() {
+            return Ok(None);
+        }
+
+        let live: HashSet<usize> = order.iter().copied().collect();
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        // the only live state without predecessors is the initial state, so it comes first
+        let mut longest: HashMap<usize, (usize, Option<(usize, V)>)> = HashMap::new();
+        longest.insert(self.initial, (0, None));
+        for s in &order {
+            let len = longest[s].0;
+            for v in &letters {
+                if let Some(t) = self.transitions[*s].get(v) {
+                    if live.contains(t) && longest.get(t).map_or(true, |(l, _)| *l < len + 1) {
+                        longest.insert(*t, (len + 1, Some((*s, *v))));
+                    }
+                }
+            }
+        }
+
+        let mut actual = *self
+            .finals
+            .iter()
+            .filter(|f| longest.contains_key(f))
+            .max_by_key(|f| (longest[f].0, std::cmp::Reverse(**f)))
+            .unwrap();
+        let mut word = Vec::new();
+        while let Some((s, v)) = longest[&actual].1 {
+            word.push(v);
+            actual = s;
+        }
+        word.reverse();
+
+        Ok(Some(word))
+    }
+
+    /// Returns the set of states which are both reachable from the initial state and coreachable from a final state.
+    pub(crate) fn live_states(&self) -> HashSet<usize> {
+        let reachable = self.reachable_states();
+        let coreachable = self.coreachable_states();
+        reachable.intersection(&coreachable).copied().collect()
+    }
+
+    /// Returns the set of states which are reachable from the initial state.
+    pub(crate) fn reachable_states(&self) -> HashSet<usize> {
+        let mut reachable = HashSet::new();
+        reachable.insert(self.initial);
+        let mut stack = vec![self.initial];
+        while let Some(e) = stack.pop() {
+            for t in self.transitions[e].values() {
+                if reachable.insert(*t) {
+                    stack.push(*t);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns the set of states from which a final state is reachable.
+    pub(crate) fn coreachable_states(&self) -> HashSet<usize> {
+        let mut predecessors = vec![Vec::new(); self.transitions.len()];
+        for (s, map) in self.transitions.iter().enumerate() {
+            for t in map.values() {
+                predecessors[*t].push(s);
+            }
+        }
+
+        let mut coreachable: HashSet<usize> = self.finals.clone();
+        let mut stack: Vec<usize> = self.finals.iter().copied().collect();
+        while let Some(e) = stack.pop() {
+            for t in &predecessors[e] {
+                if coreachable.insert(*t) {
+                    stack.push(*t);
+                }
+            }
+        }
+        coreachable
+    }
+
+    /// Returns the sub-automaton made of the states and the transitions used while running the given words, the states being numbered in the order they are visited.
+    ///
+    /// Each of the words is accepted by the result if and only if it is accepted by `self`.
+    pub fn slice_for_words(&self, words: &[Vec<V>]) -> DFA<V> {
+        let mut numbers = HashMap::new();
+        numbers.insert(self.initial, 0);
+        let mut dfa = DFA::new_empty(&self.alphabet);
+
+        for word in words {
+            let mut actual = self.initial;
+            for l in word {
+                let t = match self.transitions[actual].get(l) {
+                    Some(t) => *t,
+                    None => break,
+                };
+                let len = numbers.len();
+                let target = *numbers.entry(t).or_insert(len);
+                if target == len {
+                    dfa.transitions.push(HashMap::new());
+                }
+                dfa.transitions[numbers[&actual]].insert(*l, target);
+                actual = t;
+            }
+        }
+
+        dfa.finals = numbers
+            .iter()
+            .filter(|(s, _)| self.finals.contains(s))
+            .map(|(_, n)| *n)
+            .collect();
+        dfa
+    }
+
+    /// Returns a DFA accepting the words of length `n` accepted by `self`.
+    ///
+    /// It is built directly as the layered product of `self` with a counter, the states being the reachable pairs `(state, step)` for `step` in `0..=n`.
+    pub fn restrict_length(&self, n: usize) -> DFA<V> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut dfa = DFA::new_empty(&self.alphabet);
+        let mut layer = vec![(self.initial, 0)];
+        for _ in 0..n {
+            let mut numbers = HashMap::new();
+            let mut next = Vec::new();
+            for (s, num) in &layer {
+                for v in &letters {
+                    if let Some(t) = self.transitions[*s].get(v) {
+                        let target = *numbers.entry(*t).or_insert_with(|| {
+                            dfa.transitions.push(HashMap::new());
+                            next.push((*t, dfa.transitions.len() - 1));
+                            dfa.transitions.len() - 1
+                        });
+                        dfa.transitions[*num].insert(*v, target);
+                    }
+                }
+            }
+            layer = next;
+        }
+
+        dfa.finals = layer
+            .iter()
+            .filter(|(s, _)| self.finals.contains(s))
+            .map(|(_, num)| *num)
+            .collect();
+        dfa
+    }
+
+    /// Returns the letters with a transition from `state`.
+    ///
+    /// If `live_only` is `true`, the transitions towards states from which no final state is reachable are left out,
+    /// so that the result is the set of letters which can still lead to an accepted word.
+    pub fn enabled_symbols(&self, state: usize, live_only: bool) -> HashSet<V> {
+        let coreachable = if live_only {
+            Some(self.coreachable_states())
+        } else {
+            None
+        };
+        self.transitions[state]
+            .iter()
+            .filter(|(_, t)| coreachable.as_ref().map_or(true, |c| c.contains(t)))
+            .map(|(v, _)| *v)
+            .collect()
+    }
+
+    /// Returns whether the letter `v` is used by an accepted word, i.e. whether a transition on `v` goes from a reachable state to a coreachable state.
+    ///
+    /// The letters which aren't live can be removed from the alphabet without changing the accepted words.
+    pub fn is_symbol_live(&self, v: &V) -> bool {
+        let reachable = self.reachable_states();
+        let coreachable = self.coreachable_states();
+        reachable.iter().any(|s| {
+            self.transitions[*s]
+                .get(v)
+                .is_some_and(|t| coreachable.contains(t))
+        })
+    }
+
+    /// Trims the automaton like `trim`, and also returns the removed states, with their original indices, in increasing order.
+    ///
+    /// The first list contains the unreachable states, and the second one the reachable states from which no final state is reachable.
+    /// The initial state is always kept, so that the result is a valid automaton even if its language is empty.
+    /// The kept states are numbered in the same order as in `self`.
+    pub fn trim_reporting(self) -> (DFA<V>, Vec<usize>, Vec<usize>) {
+        let reachable = self.reachable_states();
+        let coreachable = self.coreachable_states();
+        let n = self.transitions.len();
+        let unreachable: Vec<usize> = (0..n).filter(|s| !reachable.contains(s)).collect();
+        let dead: Vec<usize> = (0..n)
+            .filter(|s| *s != self.initial && reachable.contains(s) && !coreachable.contains(s))
+            .collect();
+
+        let kept: Vec<usize> = (0..n)
+            .filter(|s| *s == self.initial || (reachable.contains(s) && coreachable.contains(s)))
+            .collect();
+        let numbers: HashMap<usize, usize> =
+            kept.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        let transitions = kept
+            .iter()
+            .map(|s| {
+                self.transitions[*s]
+                    .iter()
+                    .filter_map(|(v, t)| numbers.get(t).map(|t| (*v, *t)))
+                    .collect()
+            })
+            .collect();
+
+        let dfa = DFA {
+            alphabet: self.alphabet,
+            initial: numbers[&self.initial],
+            finals: self
+                .finals
+                .iter()
+                .filter_map(|s| numbers.get(s).copied())
+                .collect(),
+            transitions,
+        };
+        (dfa, unreachable, dead)
+    }
+
+    /// Returns the fraction of `trials` words of length `len`, drawn uniformly at random over the alphabet, which are accepted by `self`.
+    #[cfg(feature = "rand")]
+    pub fn acceptance_rate<R: rand::Rng>(&self, rng: &mut R, len: usize, trials: usize) -> f64 {
+        use rand::seq::SliceRandom;
+
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+        if trials == 0 || (letters.is_empty() && len > 0) {
+            return 0.0;
+        }
+
+        let mut word = Vec::with_capacity(len);
+        let accepted = (0..trials)
+            .filter(|_| {
+                word.clear();
+                word.extend((0..len).map(|_| *letters.choose(rng).unwrap()));
+                self.run(&word)
+            })
+            .count();
+
+        accepted as f64 / trials as f64
+    }
+
+    /// Returns a random accepted word of length at most `max_len`, or `None` if there is none.
+    ///
+    /// The word is built by a random walk from the initial state: at each step, the letters leading to a state from which a final state is reachable
+    /// within the remaining length are drawn uniformly, along with stopping if the state is final. The distribution isn't uniform over the words.
+    #[cfg(feature = "rand")]
+    pub fn sample_word<R: rand::Rng>(&self, rng: &mut R, max_len: usize) -> Option<Vec<V>> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        // the length of the shortest word leading from each state to a final state
+        let mut predecessors = vec![Vec::new(); self.transitions.len()];
+        for (s, map) in self.transitions.iter().enumerate() {
+            for t in map.values() {
+                predecessors[*t].push(s);
+            }
+        }
+        let mut distances = vec![None; self.transitions.len()];
+        let mut queue: VecDeque<usize> = self.finals.iter().copied().collect();
+        for f in &self.finals {
+            distances[*f] = Some(0);
+        }
+        while let Some(s) = queue.pop_front() {
+            let d = distances[s].unwrap();
+            for p in &predecessors[s] {
+                if distances[*p].is_none() {
+                    distances[*p] = Some(d + 1);
+                    queue.push_back(*p);
+                }
+            }
+        }
+
+        if distances[self.initial].map_or(true, |d| d > max_len) {
+            return None;
+        }
+
+        let mut word = Vec::new();
+        let mut actual = self.initial;
+        loop {
+            let remaining = max_len - word.len();
+            let choices: Vec<(V, usize)> = letters
+                .iter()
+                .filter_map(|v| self.transitions[actual].get(v).map(|t| (*v, *t)))
+                .filter(|(_, t)| distances[*t].is_some_and(|d| d < remaining))
+                .collect();
+            let stop = self.finals.contains(&actual) as usize;
+            let choice = rng.gen_range(0..choices.len() + stop);
+            if choice == choices.len() {
+                return Some(word);
+            }
+            word.push(choices[choice].0);
+            actual = choices[choice].1;
+        }
+    }
+
+    /// Returns the number of accepted words of length `n`.
+    ///
+    /// The computation is done with `u128` and wraps around on overflow (see `count_words_big` with the `num-bigint` feature).
+    pub fn count_words(&self, n: usize) -> u128 {
+        self.growth(n)[n]
+    }
+
+    /// Returns the number of accepted words of length `n`, like [`count_words`](#method.count_words) but with arbitrary-precision integers, so it never overflows.
+    #[cfg(feature = "num-bigint")]
+    pub fn count_words_big(&self, n: usize) -> num_bigint::BigUint {
+        use num_bigint::BigUint;
+
+        let mut paths = vec![BigUint::from(0u32); self.transitions.len()];
+        paths[self.initial] = BigUint::from(1u32);
+        for _ in 0..n {
+            let mut next = vec![BigUint::from(0u32); self.transitions.len()];
+            for (s, map) in self.transitions.iter().enumerate() {
+                for t in map.values() {
+                    next[*t] += &paths[s];
+                }
+            }
+            paths = next;
+        }
+
+        self.finals.iter().map(|f| &paths[*f]).sum()
+    }
+
+    /// Returns the Parikh vectors of the accepted words of length at most `max_total`, i.e. the number of occurrences of each letter, the letters being sorted.
+    ///
+    /// The Parikh image of the whole language is infinite as soon as the language is, so the length of the words has to be bounded.
+    pub fn parikh_image_up_to(&self, max_total: usize) -> HashSet<Vec<usize>> {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut image = HashSet::new();
+        let mut actuals: HashSet<(usize, Vec<usize>)> = HashSet::new();
+        actuals.insert((self.initial, vec![0; letters.len()]));
+        for length in 0..=max_total {
+            for (s, counts) in &actuals {
+                if self.finals.contains(s) {
+                    image.insert(counts.clone());
+                }
+            }
+            if length == max_total {
+                break;
+            }
+
+            let mut next = HashSet::new();
+            for (s, counts) in &actuals {
+                for (i, v) in letters.iter().enumerate() {
+                    if let Some(t) = self.transitions[*s].get(v) {
+                        let mut counts = counts.clone();
+                        counts[i] += 1;
+                        next.insert((*t, counts));
+                    }
+                }
+            }
+            actuals = next;
+        }
+
+        image
+    }
+
+    /// Returns the numbers of accepted words of each length from `0` to `up_to` (included), computed in a single pass.
+    ///
+    /// The computation is done with `u128` and wraps around on overflow.
+    pub fn growth(&self, up_to: usize) -> Vec<u128> {
+        self.growth_with(up_to, |a, b| Some(a.wrapping_add(b)))
+            .unwrap()
+    }
+
+    // the numbers of accepted words of each length up to `up_to`, summed with `add`, or `None` as soon as `add` fails
+    fn growth_with<F: Fn(u128, u128) -> Option<u128>>(
+        &self,
+        up_to: usize,
+        add: F,
+    ) -> Option<Vec<u128>> {
+        let mut paths = vec![0u128; self.transitions.len()];
+        paths[self.initial] = 1;
+
+        let mut growth = Vec::with_capacity(up_to + 1);
+        for n in 0..=up_to {
+            growth.push(
+                self.finals
+                    .iter()
+                    .try_fold(0u128, |acc, f| add(acc, paths[*f]))?,
+            );
+            if n == up_to {
+                break;
+            }
+
+            let mut next = vec![0u128; self.transitions.len()];
+            for (s, map) in self.transitions.iter().enumerate() {
+                for t in map.values() {
+                    next[*t] = add(next[*t], paths[s])?;
+                }
+            }
+            paths = next;
+        }
+
+        Some(growth)
+    }
+
+    /// Returns the minimal automaton of the language of `self`, with states renumbered by a breadth-first search from the initial state where the letters are visited in order.
+    ///
+    /// Two automata have the same result (up to their alphabets) if and only if they accept the same language.
+    pub fn canonical(&self) -> DFA<V> {
+        let mut sorted: Vec<V> = self.alphabet.iter().copied().collect();
+        sorted.sort();
+        self.clone().minimize().renumbered(&sorted, &sorted)
+    }
+
+    /// Returns whether the [`canonical`](#method.canonical) automata of `self` and `other` are identical, alphabets included.
+    ///
+    /// Unlike `==`, two automata accepting the same words over different alphabets aren't structurally equal.
+    pub fn structurally_eq(&self, other: &DFA<V>) -> bool {
+        let (a, b) = (self.canonical(), other.canonical());
+        a.alphabet == b.alphabet && a.finals == b.finals && a.transitions == b.transitions
+    }
+
+    /// Returns the minimal automaton of the language of `self`, with states renumbered by a breadth-first search from the initial state, and with letters renamed by the permutation of the alphabet giving the lexicographically smallest transition table.
+    ///
+    /// Two automata whose languages are equal up to a renaming of the letters have the same result.
+    /// Every permutation of the alphabet is tried, so the cost is factorial in the size of the alphabet.
+    ///
+    /// # Panics
+    /// Panics if the alphabet has more than [`MAX_RELABELING_ALPHABET`](./constant.MAX_RELABELING_ALPHABET.html) letters.
+    pub fn canonical_up_to_relabeling(&self) -> DFA<V> {
+        assert!(
+            self.alphabet.len() <= MAX_RELABELING_ALPHABET,
+            "the alphabet is too big to try every permutation"
+        );
+
+        let minimal = self.clone().minimize();
+        let mut sorted: Vec<V> = self.alphabet.iter().copied().collect();
+        sorted.sort();
+
+        // for each state, whether it is final and its targets over the sorted alphabet
+        type Table = Vec<(bool, Vec<Option<usize>>)>;
+        let mut best: Option<(Table, DFA<V>)> = None;
+        let mut permutation = sorted.clone();
+        permutations(&mut permutation, 0, &mut |letters| {
+            let dfa = minimal.renumbered(letters, &sorted);
+            let table: Table = dfa
+                .transitions
+                .iter()
+                .enumerate()
+                .map(|(s, map)| {
+                    let targets = sorted.iter().map(|v| map.get(v).copied()).collect();
+                    (dfa.finals.contains(&s), targets)
+                })
+                .collect();
+            if best.as_ref().map_or(true, |(t, _)| table < *t) {
+                best = Some((table, dfa));
+            }
+        });
+
+        best.unwrap().1
+    }
+
+    /// Returns the automaton restricted to its reachable states, numbered by a breadth-first search from the initial state where the letters are visited in the order of `letters`, the letter `letters[i]` being renamed `names[i]`.
+    pub(crate) fn renumbered(&self, letters: &[V], names: &[V]) -> DFA<V> {
+        let mut numbers = HashMap::new();
+        let mut queue = VecDeque::new();
+        numbers.insert(self.initial, 0);
+        queue.push_back(self.initial);
+
+        let mut dfa = DFA::new_empty(&names.iter().copied().collect());
+        dfa.alphabet
+            .extend(self.alphabet.difference(&letters.iter().copied().collect()));
+        while let Some(s) = queue.pop_front() {
+            let num = numbers[&s];
+            if self.finals.contains(&s) {
+                dfa.finals.insert(num);
+            }
+            for (v, name) in letters.iter().zip(names) {
+                if let Some(t) = self.transitions[s].get(v) {
+                    let l = numbers.len();
+                    let target = *numbers.entry(*t).or_insert_with(|| {
+                        queue.push_back(*t);
+                        l
+                    });
+                    if target == l {
+                        dfa.transitions.push(HashMap::new());
+                    }
+                    dfa.transitions[num].insert(*name, target);
+                }
+            }
+        }
+
+        dfa
+    }
+
+    /// Returns the number of characters of `self.to_regex().simplify().to_string()`, without building the string.
+    ///
+    /// This tells whether the regex or the automaton is the more compact representation of the language.
+    pub fn regex_size_estimate(&self) -> usize {
+        self.to_regex().simplify().printed_len()
+    }
+
     /// Returns a string containing the dot description of the automaton
     pub fn to_dot(&self) -> String {
         self.to_nfa().to_dot()
     }
 
+    /// Writes the dot description of the automaton to `w`, without building it in memory.
+    pub fn write_dot<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.to_nfa().write_dot(w)
+    }
+
+    /// Returns the graph of the automaton, where the node `i` is the state `i` and each edge is labelled by the letter of its transition.
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(&self) -> petgraph::Graph<StateInfo, V> {
+        let mut graph = petgraph::Graph::with_capacity(self.transitions.len(), 0);
+        let nodes: Vec<_> = (0..self.transitions.len())
+            .map(|s| {
+                graph.add_node(StateInfo {
+                    is_initial: s == self.initial,
+                    is_final: self.finals.contains(&s),
+                })
+            })
+            .collect();
+
+        for (s, map) in self.transitions.iter().enumerate() {
+            let mut letters: Vec<_> = map.iter().collect();
+            letters.sort();
+            for (v, t) in letters {
+                graph.add_edge(nodes[s], nodes[*t], *v);
+            }
+        }
+
+        graph
+    }
+
+    /// Returns a string containing the Mermaid `stateDiagram-v2` description of the automaton.
+    pub fn to_mermaid(&self) -> String {
+        self.to_nfa().to_mermaid()
+    }
+
+    /// Returns a string containing the description of the automaton in the AT&T FSM format.
+    pub fn to_att(&self) -> String {
+        let mut ret = Vec::new();
+        self.write_att(&mut ret).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the description of the automaton in the AT&T FSM format to `w`, without building it in memory.
+    ///
+    /// Each transition is written as a line `source target letter`, those of the initial state coming first,
+    /// then each final state is written on its own line.
+    pub fn write_att<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let others = (0..self.transitions.len()).filter(|s| *s != self.initial);
+        for s in std::iter::once(self.initial).chain(others) {
+            let mut letters: Vec<_> = self.transitions[s].iter().collect();
+            letters.sort();
+            for (v, t) in letters {
+                writeln!(w, "{}\t{}\t{}", s, t, v)?;
+            }
+        }
+
+        let mut finals: Vec<_> = self.finals.iter().collect();
+        finals.sort();
+        for f in finals {
+            writeln!(w, "{}", f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a string containing the transition table of the automaton.
+    pub fn to_table(&self) -> String {
+        let mut ret = Vec::new();
+        self.write_table(&mut ret).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the transition table of the automaton to `w`, without building it in memory.
+    ///
+    /// The columns are separated by tabulations, the first one being the letters in order.
+    /// Each state has its own row, marked by `->` if it is initial and by `*` if it is final, and the missing transitions are written `-`.
+    pub fn write_table<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut letters: Vec<_> = self.alphabet.iter().collect();
+        letters.sort();
+
+        write!(w, "\t")?;
+        for v in &letters {
+            write!(w, "\t{}", v)?;
+        }
+        writeln!(w)?;
+
+        for (s, map) in self.transitions.iter().enumerate() {
+            let initial = if s == self.initial { "->" } else { "" };
+            let fin = if self.finals.contains(&s) { "*" } else { "" };
+            write!(w, "{}{}\t{}", initial, fin, s)?;
+            for v in &letters {
+                match map.get(v) {
+                    Some(t) => write!(w, "\t{}", t)?,
+                    None => write!(w, "\t-")?,
+                }
+            }
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+
+    /// Makes `s` the initial state, returning an error if it isn't a state of the automaton.
+    pub fn set_initial(&mut self, s: usize) -> Result<(), String> {
+        self.check_state(s)?;
+        self.initial = s;
+        Ok(())
+    }
+
+    /// Makes `s` a final state, returning an error if it isn't a state of the automaton.
+    pub fn add_final(&mut self, s: usize) -> Result<(), String> {
+        self.check_state(s)?;
+        self.finals.insert(s);
+        Ok(())
+    }
+
+    /// Makes `s` a non-final state, returning an error if it isn't a state of the automaton.
+    pub fn remove_final(&mut self, s: usize) -> Result<(), String> {
+        self.check_state(s)?;
+        self.finals.remove(&s);
+        Ok(())
+    }
+
+    fn check_state(&self, s: usize) -> Result<(), String> {
+        if s < self.transitions.len() {
+            Ok(())
+        } else {
+            Err(format!("the state {} doesn't exist", s))
+        }
+    }
+
     /// Returns an empty automaton with the given alphabet.
     pub fn new_empty(alphabet: &HashSet<V>) -> DFA<V> {
         DFA {
@@ -56,6 +1523,141 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> DFA<V> {
         }
     }
 
+    /// Returns a complete automaton that accepts a word if and only if it doesn't contain `pat` as a factor.
+    ///
+    /// The automaton is built directly from the failure function of the [Knuth-Morris-Pratt algorithm](https://en.wikipedia.org/wiki/Knuth%E2%80%93Morris%E2%80%93Pratt_algorithm), and has `pat.len() + 1` states.
+    pub fn excluding_substring(alphabet: HashSet<V>, pat: &[V]) -> DFA<V> {
+        if pat.iter().any(|v| !alphabet.contains(v)) {
+            return DFA::new_empty(&alphabet).negate();
+        }
+
+        let m = pat.len();
+        let mut transitions: Vec<HashMap<V, usize>> = vec![HashMap::new(); m + 1];
+
+        // the state i means that the longest suffix of the input which is a prefix of `pat` has length i
+        let mut restart = 0;
+        for (i, p) in pat.iter().enumerate() {
+            for v in &alphabet {
+                let t = if i == 0 { 0 } else { transitions[restart][v] };
+                transitions[i].insert(*v, t);
+            }
+            transitions[i].insert(*p, i + 1);
+            if i > 0 {
+                restart = transitions[restart][p];
+            }
+        }
+        for v in &alphabet {
+            transitions[m].insert(*v, m);
+        }
+
+        DFA {
+            alphabet,
+            initial: 0,
+            finals: (m..=m).collect(),
+            transitions,
+        }
+        .negate()
+    }
+
+    /// Returns an automaton accepting exactly the factors of `word` (including the empty word), the letters of `word` being added to the alphabet.
+    ///
+    /// The automaton is built online with the [suffix automaton](https://en.wikipedia.org/wiki/Suffix_automaton) construction, and has at most `2 * word.len()` states.
+    /// All its states are final: it is the minimal automaton of the suffixes of `word`, but it may have a few more states than the minimal automaton of its factors.
+    pub fn suffix_automaton(mut alphabet: HashSet<V>, word: &[V]) -> DFA<V> {
+        alphabet.extend(word.iter().copied());
+        let mut dfa = DFA::new_empty(&alphabet);
+        // the length of the longest factor reaching each state, and its suffix link
+        let mut len = vec![0];
+        let mut link: Vec<Option<usize>> = vec![None];
+        let mut last = 0;
+
+        for v in word {
+            let actual = dfa.transitions.len();
+            dfa.transitions.push(HashMap::new());
+            len.push(len[last] + 1);
+            link.push(Some(0));
+
+            let mut p = Some(last);
+            while let Some(q) = p {
+                if dfa.transitions[q].contains_key(v) {
+                    break;
+                }
+                dfa.transitions[q].insert(*v, actual);
+                p = link[q];
+            }
+
+            if let Some(p) = p {
+                let q = dfa.transitions[p][v];
+                if len[p] + 1 == len[q] {
+                    link[actual] = Some(q);
+                } else {
+                    let clone = dfa.transitions.len();
+                    dfa.transitions.push(dfa.transitions[q].clone());
+                    len.push(len[p] + 1);
+                    link.push(link[q]);
+
+                    let mut p = Some(p);
+                    while let Some(r) = p {
+                        if dfa.transitions[r].get(v) != Some(&q) {
+                            break;
+                        }
+                        dfa.transitions[r].insert(*v, clone);
+                        p = link[r];
+                    }
+                    link[q] = Some(clone);
+                    link[actual] = Some(clone);
+                }
+            }
+            last = actual;
+        }
+
+        dfa.finals = (0..dfa.transitions.len()).collect();
+        dfa
+    }
+
+    /// Returns the prefix tree automaton accepting exactly the given words, the letters of the words being added to the alphabet.
+    pub fn from_words(mut alphabet: HashSet<V>, words: &[Vec<V>]) -> DFA<V> {
+        let mut dfa = DFA::new_empty(&alphabet);
+        for word in words {
+            let mut actual = dfa.initial;
+            for l in word {
+                alphabet.insert(*l);
+                let len = dfa.transitions.len();
+                actual = *dfa.transitions[actual].entry(*l).or_insert(len);
+                if actual == len {
+                    dfa.transitions.push(HashMap::new());
+                }
+            }
+            dfa.finals.insert(actual);
+        }
+        dfa.alphabet = alphabet;
+        dfa
+    }
+
+    /// Returns a complete automaton accepting every word of `accept` and rejecting every other word (in particular the words of `reject`).
+    ///
+    /// Returns an error if a word is both in `accept` and in `reject`, or if a word contains a letter which isn't in the alphabet.
+    pub fn from_examples(
+        alphabet: HashSet<V>,
+        accept: &[Vec<V>],
+        reject: &[Vec<V>],
+    ) -> Result<DFA<V>, String> {
+        if let Some(l) = accept
+            .iter()
+            .chain(reject.iter())
+            .flatten()
+            .find(|l| !alphabet.contains(l))
+        {
+            return Err(format!("the letter {} is not in the alphabet", l));
+        }
+
+        if let Some(w) = reject.iter().find(|w| accept.contains(w)) {
+            return Err(format!("the word {:?} is both accepted and rejected", w));
+        }
+
+        Ok(DFA::from_words(alphabet, accept).complete())
+    }
+
     /// Returns an automaton built from the raw arguments.
     pub fn from_raw(
         alphabet: HashSet<V>,
@@ -94,6 +1696,26 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> DFA<V> {
     }
 }
 
+impl DFA<char> {
+    /// Parses the regex pattern and returns an automaton accepting the same language, the alphabet being the set of letters appearing in it.
+    pub fn from_pattern(s: &str) -> Result<DFA<char>, ParseError> {
+        Ok(Regex::from_pattern(s)?.to_dfa())
+    }
+
+    /// Returns whether the automaton accepts the characters of `s`, without collecting them first.
+    pub fn run_str(&self, s: &str) -> bool {
+        let mut actual = self.initial;
+        for l in s.chars() {
+            if let Some(t) = self.transitions[actual].get(&l) {
+                actual = *t;
+            } else {
+                return false;
+            }
+        }
+        self.finals.contains(&actual)
+    }
+}
+
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for DFA<V> {
     fn run(&self, v: &[V]) -> bool {
         let mut actual = self.initial;
@@ -189,7 +1811,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for DFA<V>
     }
 
     fn reverse(self) -> DFA<V> {
-        self.to_nfa().reverse().to_dfa()
+        self.reverse_nfa().to_dfa()
     }
 }
 
@@ -226,8 +1848,87 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToDfa<V> for DFA<V> {
 }
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToRegex<V> for DFA<V> {
+    /// Solves the system of equations `L_i = (a L_δ(i, a) | ...)` (with `ε` added for the final states) with Arden's lemma `X = AX|B => X = A*B`,
+    /// eliminating the live states other than the initial one in decreasing order of their numbers.
     fn to_regex(&self) -> Regex<V> {
-        self.to_nfa().to_regex()
+        let live = self.live_states();
+        if !live.contains(&self.initial) {
+            return Regex {
+                alphabet: self.alphabet.clone(),
+                regex: Operations::Empty,
+            };
+        }
+
+        let mut states: Vec<usize> = live.into_iter().filter(|s| *s != self.initial).collect();
+        states.sort_unstable();
+        states.insert(0, self.initial);
+        let numbers: HashMap<usize, usize> =
+            states.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        let n = states.len();
+
+        let mut labels = vec![vec![BTreeSet::new(); n]; n];
+        for (i, s) in states.iter().enumerate() {
+            for (v, t) in &self.transitions[*s] {
+                if let Some(j) = numbers.get(t) {
+                    labels[i][*j].insert(*v);
+                }
+            }
+        }
+        let mut a: Vec<Vec<Operations<V>>> = labels
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|letters| {
+                        if !letters.is_empty() && letters.len() == self.alphabet.len() {
+                            Operations::Dot(None)
+                        } else {
+                            letters
+                                .into_iter()
+                                .fold(Operations::Empty, |acc, v| acc + Operations::Letter(v))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut b: Vec<Operations<V>> = states
+            .iter()
+            .map(|s| {
+                if self.finals.contains(s) {
+                    Operations::Epsilon
+                } else {
+                    Operations::Empty
+                }
+            })
+            .collect();
+
+        let star = |o: Operations<V>| match o {
+            Operations::Empty => Operations::Epsilon,
+            o => Operations::Repeat(Box::new(o), 0, None),
+        };
+        for k in (1..n).rev() {
+            // L_k = A_kk* (A_k0 L_0 | ... | B_k), substituted in the equations of the remaining states
+            let (rows, rest) = a.split_at_mut(k);
+            let row_k = &rest[0];
+            let loops = star(row_k[k].clone());
+            for (i, row) in rows.iter_mut().enumerate() {
+                if row[k] == Operations::Empty {
+                    continue;
+                }
+                let factor = row[k].clone() * loops.clone();
+                for (o, added) in row.iter_mut().zip(row_k).take(k) {
+                    if *added != Operations::Empty {
+                        *o += factor.clone() * added.clone();
+                    }
+                }
+                let added = factor * b[k].clone();
+                b[i] += added;
+            }
+        }
+
+        Regex {
+            alphabet: self.alphabet.clone(),
+            regex: star(a[0][0].clone()) * b[0].clone(),
+        }
     }
 }
 
@@ -244,16 +1945,87 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToNfa<V> for DFA<V> {
             initials,
             finals: self.finals.clone(),
             transitions,
+            epsilon: HashMap::new(),
         }
     }
 }
 
+// the fields of a serialized DFA, before their validation
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "DFA")]
+struct RawDFA<V: Eq + Hash> {
+    alphabet: HashSet<V>,
+    initial: usize,
+    finals: HashSet<usize>,
+    transitions: Vec<HashMap<V, usize>>,
+}
+
+/// The deserialized automaton is validated by [`from_raw`](./struct.DFA.html#method.from_raw), whose error is returned if a state or a letter is invalid.
+#[cfg(feature = "serde")]
+impl<'de, V> serde::Deserialize<'de> for DFA<V>
+where
+    V: Eq + Hash + Display + Copy + Clone + Debug + Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawDFA::deserialize(deserializer)?;
+        DFA::from_raw(raw.alphabet, raw.initial, raw.finals, raw.transitions)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Displays the [transition table](./struct.DFA.html#method.to_table) of the automaton.
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Display for DFA<V> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.to_table())
+    }
+}
+
+/// The equality of the languages, the words being taken over the union of the alphabets (see [`contains`](./struct.DFA.html#method.contains)):
+/// two automata over different alphabets are equal if they accept the same words.
+/// See [`structurally_eq`](./struct.DFA.html#method.structurally_eq) to also compare the alphabets.
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialEq<DFA<V>> for DFA<V> {
     fn eq(&self, b: &DFA<V>) -> bool {
+        // cheap checks first: the acceptance of the empty word, and the lengths of the shortest accepted and rejected words (which also decide emptiness and fullness)
+        if self.alphabet == b.alphabet {
+            if self.finals.contains(&self.initial) != b.finals.contains(&b.initial) {
+                return false;
+            }
+
+            let shortest_accepted = |a: &DFA<V>| {
+                a.product_shortest_word(a, (a.initial, a.initial), |x, _| x)
+                    .map(|w| w.len())
+            };
+            if shortest_accepted(self) != shortest_accepted(b) {
+                return false;
+            }
+
+            let shortest_rejected = |a: &DFA<V>| a.shortest_rejected().map(|w| w.len());
+            if shortest_rejected(self) != shortest_rejected(b) {
+                return false;
+            }
+        }
+
         self.le(&b) && self.ge(&b)
     }
 }
 
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Eq for DFA<V> {}
+
+/// The hash of the [`canonical`](./struct.DFA.html#method.canonical) automaton, so that two automata accepting the same language have the same hash.
+///
+/// Each hash minimizes the automaton.
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Hash for DFA<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let canonical = self.canonical();
+        for (s, map) in canonical.transitions.iter().enumerate() {
+            let mut targets: Vec<(V, usize)> = map.iter().map(|(v, t)| (*v, *t)).collect();
+            targets.sort();
+            (canonical.finals.contains(&s), targets).hash(state);
+        }
+    }
+}
+
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialEq<NFA<V>> for DFA<V> {
     fn eq(&self, b: &NFA<V>) -> bool {
         self.to_nfa().eq(b)
@@ -262,7 +2034,14 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialEq<NFA<V>> for
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialEq<Regex<V>> for DFA<V> {
     fn eq(&self, b: &Regex<V>) -> bool {
-        self.to_nfa().eq(&b.to_nfa())
+        let dfa = b.to_nfa().to_dfa();
+        if self.alphabet != dfa.alphabet {
+            return self.to_nfa().eq(&dfa.to_nfa());
+        }
+
+        // the regex is determinized once, then the product is searched for a word accepted by only one of them
+        self.product_shortest_word(&dfa, (self.initial, dfa.initial), |x, y| x != y)
+            .is_none()
     }
 }
 
@@ -334,7 +2113,8 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Not for DFA<V> {
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Sub for DFA<V> {
     type Output = Self;
 
-    fn sub(self, other: DFA<V>) -> DFA<V> {
+    fn sub(mut self, mut other: DFA<V>) -> DFA<V> {
+        share_alphabets(&mut self.alphabet, &mut other.alphabet);
         self.intersect(other.negate())
     }
 }
@@ -347,3 +2127,16 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Add for DFA<V> {
         self.unite(other)
     }
 }
+
+// calls `f` on every permutation of `v[k..]` (the beginning of `v` being left unchanged)
+fn permutations<V, F: FnMut(&[V])>(v: &mut [V], k: usize, f: &mut F) {
+    if k == v.len() {
+        f(v);
+        return;
+    }
+    for i in k..v.len() {
+        v.swap(k, i);
+        permutations(v, k + 1, f);
+        v.swap(k, i);
+    }
+}
@@ -6,5 +6,10 @@ mod utils;
 
 pub mod automaton;
 pub mod dfa;
+pub mod mealy;
+pub mod moore;
 pub mod nfa;
+mod parser;
 pub mod regex;
+pub mod tokenizer;
+pub mod weighted;
@@ -0,0 +1,106 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// <https://en.wikipedia.org/wiki/Mealy_machine>
+///
+/// A deterministic automaton where each transition has an output: running a word outputs the sequence of the outputs of the transitions taken.
+#[derive(Debug, Clone)]
+pub struct MealyMachine<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Clone> {
+    pub(crate) alphabet: HashSet<V>,
+    pub(crate) initial: usize,
+    pub(crate) transitions: Vec<HashMap<V, (usize, W)>>,
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Clone> MealyMachine<V, W> {
+    /// Returns the machine with the given states, reading `v` in the state `i` leading to the state `transitions[i][v].0` and outputting `transitions[i][v].1`.
+    ///
+    /// Returns an error if a state or a letter is invalid.
+    pub fn new(
+        alphabet: HashSet<V>,
+        initial: usize,
+        transitions: Vec<HashMap<V, (usize, W)>>,
+    ) -> Result<MealyMachine<V, W>, String> {
+        if initial >= transitions.len() {
+            return Err(format!("the state {} doesn't exist", initial));
+        }
+        for map in &transitions {
+            for (v, (t, _)) in map {
+                if !alphabet.contains(v) {
+                    return Err(format!("the letter {} is not in the alphabet", v));
+                }
+                if *t >= transitions.len() {
+                    return Err(format!("the state {} doesn't exist", t));
+                }
+            }
+        }
+
+        Ok(MealyMachine {
+            alphabet,
+            initial,
+            transitions,
+        })
+    }
+
+    /// Returns the outputs of the transitions taken while reading `input`, one per letter, or `None` if a transition is missing.
+    pub fn transduce(&self, input: &[V]) -> Option<Vec<W>> {
+        let mut actual = self.initial;
+        let mut outputs = Vec::with_capacity(input.len());
+        for l in input {
+            let (t, w) = self.transitions[actual].get(l)?;
+            actual = *t;
+            outputs.push(w.clone());
+        }
+        Some(outputs)
+    }
+
+    /// Returns whether every state has a transition for every letter of the alphabet, i.e. whether `transduce` succeeds on every word over the alphabet.
+    pub fn is_complete(&self) -> bool {
+        self.transitions
+            .iter()
+            .all(|map| self.alphabet.iter().all(|v| map.contains_key(v)))
+    }
+
+    /// Returns the set of states reachable from the initial state.
+    pub(crate) fn reachable_states(&self) -> HashSet<usize> {
+        let mut reachable = HashSet::new();
+        reachable.insert(self.initial);
+        let mut stack = vec![self.initial];
+        while let Some(e) = stack.pop() {
+            for (t, _) in self.transitions[e].values() {
+                if reachable.insert(*t) {
+                    stack.push(*t);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Removes the states which aren't reachable from the initial state, the remaining ones keeping their order.
+    pub fn make_reachable(mut self) -> MealyMachine<V, W> {
+        let reachable = self.reachable_states();
+        let mut map = HashMap::new();
+        for s in 0..self.transitions.len() {
+            if reachable.contains(&s) {
+                map.insert(s, map.len());
+            }
+        }
+
+        self.transitions = self
+            .transitions
+            .into_iter()
+            .enumerate()
+            .filter(|(s, _)| reachable.contains(s))
+            .map(|(_, transitions)| {
+                transitions
+                    .into_iter()
+                    .map(|(v, (t, w))| (v, (map[&t], w)))
+                    .collect()
+            })
+            .collect();
+        self.initial = map[&self.initial];
+        self
+    }
+}
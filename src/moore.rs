@@ -0,0 +1,111 @@
+use crate::{dfa::DFA, utils::reachable_product};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// <https://en.wikipedia.org/wiki/Moore_machine>
+///
+/// A deterministic automaton where each state has an output: running a word outputs the sequence of the outputs of the states reached after each letter.
+#[derive(Debug, Clone)]
+pub struct MooreMachine<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Clone> {
+    pub(crate) alphabet: HashSet<V>,
+    pub(crate) initial: usize,
+    pub(crate) outputs: Vec<W>,
+    pub(crate) transitions: Vec<HashMap<V, usize>>,
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Clone> MooreMachine<V, W> {
+    /// Returns the machine with the given states, the state `i` having the output `outputs[i]` and the transitions `transitions[i]`.
+    ///
+    /// Returns an error if the numbers of outputs and of transitions differ, or if a state or a letter is invalid.
+    pub fn new(
+        alphabet: HashSet<V>,
+        initial: usize,
+        outputs: Vec<W>,
+        transitions: Vec<HashMap<V, usize>>,
+    ) -> Result<MooreMachine<V, W>, String> {
+        if outputs.len() != transitions.len() {
+            return Err(format!(
+                "there are {} outputs for {} states",
+                outputs.len(),
+                transitions.len()
+            ));
+        }
+        if initial >= transitions.len() {
+            return Err(format!("the state {} doesn't exist", initial));
+        }
+        for map in &transitions {
+            for (v, t) in map {
+                if !alphabet.contains(v) {
+                    return Err(format!("the letter {} is not in the alphabet", v));
+                }
+                if *t >= transitions.len() {
+                    return Err(format!("the state {} doesn't exist", t));
+                }
+            }
+        }
+
+        Ok(MooreMachine {
+            alphabet,
+            initial,
+            outputs,
+            transitions,
+        })
+    }
+
+    /// Returns the outputs of the states reached after each letter of `input` (the output of the initial state isn't included),
+    /// or `None` if a transition is missing.
+    pub fn run(&self, input: &[V]) -> Option<Vec<W>> {
+        let mut actual = self.initial;
+        let mut outputs = Vec::with_capacity(input.len());
+        for l in input {
+            actual = *self.transitions[actual].get(l)?;
+            outputs.push(self.outputs[actual].clone());
+        }
+        Some(outputs)
+    }
+
+    /// Returns the machine feeding the outputs of `self` into `next`, built as the product of the reachable pairs of states.
+    ///
+    /// Running a word on the result outputs the outputs of `next` running the outputs of `self`.
+    pub fn compose<X: Clone>(self, next: MooreMachine<W, X>) -> MooreMachine<V, X>
+    where
+        W: Eq + Hash + Display + Copy + Debug + Ord,
+    {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let (pairs, transitions) =
+            reachable_product(&letters, (self.initial, next.initial), |(p, q), v| {
+                let p = *self.transitions[p].get(v)?;
+                let q = *next.transitions[q].get(&self.outputs[p])?;
+                Some((p, q))
+            });
+
+        MooreMachine {
+            outputs: pairs
+                .into_iter()
+                .map(|(_, q)| next.outputs[q].clone())
+                .collect(),
+            alphabet: self.alphabet,
+            initial: 0,
+            transitions,
+        }
+    }
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> MooreMachine<V, bool> {
+    /// Returns the machine whose outputs tell whether the states of `dfa` are final.
+    pub fn from_dfa(dfa: &DFA<V>) -> MooreMachine<V, bool> {
+        MooreMachine {
+            alphabet: dfa.alphabet.clone(),
+            initial: dfa.initial,
+            outputs: (0..dfa.transitions.len())
+                .map(|s| dfa.finals.contains(&s))
+                .collect(),
+            transitions: dfa.transitions.clone(),
+        }
+    }
+}
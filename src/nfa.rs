@@ -1,25 +1,30 @@
 use crate::{
     automaton::{Automata, Automaton, Buildable, FromRawError},
     dfa::{ToDfa, DFA},
-    regex::{Operations, Regex, ToRegex},
+    regex::{Operations, ParseError, Regex, ToRegex},
     utils::*,
 };
 use std::{
     cmp::{Ordering, Ordering::*, PartialEq, PartialOrd},
-    collections::{BTreeSet, HashMap, HashSet, VecDeque},
-    fmt::{Debug, Display},
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     hash::Hash,
-    iter::{repeat, FromIterator},
-    ops::{Add, BitOr, Bound::*, Mul, Neg, Not, RangeBounds, Sub},
+    io::{self, Write},
+    iter::repeat,
+    ops::{Add, Bound::*, Mul, Neg, Not, RangeBounds, Sub},
+    str::FromStr,
 };
 
 /// <https://en.wikipedia.org/wiki/Nondeterministic_finite_automaton>
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NFA<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     pub(crate) alphabet: HashSet<V>,
     pub(crate) initials: HashSet<usize>,
     pub(crate) finals: HashSet<usize>,
     pub(crate) transitions: Vec<HashMap<V, Vec<usize>>>,
+    /// The epsilon transitions, which can be taken without reading any letter (a state without epsilon transitions has no entry).
+    pub(crate) epsilon: HashMap<usize, Vec<usize>>,
 }
 
 /// An interface for structs that can be converted into a NFA.
@@ -27,171 +32,889 @@ pub trait ToNfa<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
     fn to_nfa(&self) -> NFA<V>;
 }
 
+/// An interface for the keys identifying a set of states during the subset construction (see [`to_dfa_with_key`](./struct.NFA.html#method.to_dfa_with_key)).
+///
+/// Bitmasks (`u32`, `u64` and `u128`) and `BTreeSet<usize>` are provided.
+pub trait SubsetKey: Eq + Hash {
+    /// Returns the key of the empty set.
+    fn empty() -> Self;
+    /// Adds the state to the set.
+    fn insert(&mut self, state: usize);
+}
+
+macro_rules! bitmask_subset_key {
+    ($t: ty) => {
+        impl SubsetKey for $t {
+            fn empty() -> Self {
+                0
+            }
+
+            fn insert(&mut self, state: usize) {
+                *self |= (1 as $t)
+                    .checked_shl(state as u32)
+                    .expect("the state doesn't fit in the bitmask");
+            }
+        }
+    };
+}
+
+bitmask_subset_key!(u32);
+bitmask_subset_key!(u64);
+bitmask_subset_key!(u128);
+
+impl SubsetKey for BTreeSet<usize> {
+    fn empty() -> Self {
+        BTreeSet::new()
+    }
+
+    fn insert(&mut self, state: usize) {
+        BTreeSet::insert(self, state);
+    }
+}
+
+/// A builder adding the states and the transitions of a NFA one by one, returned by [`NFA::builder`](./struct.NFA.html#method.builder).
+#[derive(Debug, Clone)]
+pub struct NFABuilder<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> {
+    alphabet: HashSet<V>,
+    initials: HashSet<usize>,
+    finals: HashSet<usize>,
+    states: usize,
+    transitions: Vec<(usize, V, usize)>,
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFABuilder<V> {
+    /// Adds a state without any transition and returns its number.
+    pub fn add_state(&mut self) -> usize {
+        self.states += 1;
+        self.states - 1
+    }
+
+    /// Adds a transition from `source` to `target` labelled by `letter`, the letter and the states being checked by [`build`](#method.build).
+    pub fn add_transition(&mut self, source: usize, letter: V, target: usize) -> &mut Self {
+        self.transitions.push((source, letter, target));
+        self
+    }
+
+    /// Makes `state` initial.
+    pub fn set_initial(&mut self, state: usize) -> &mut Self {
+        self.initials.insert(state);
+        self
+    }
+
+    /// Makes `state` final.
+    pub fn set_final(&mut self, state: usize) -> &mut Self {
+        self.finals.insert(state);
+        self
+    }
+
+    /// Returns the NFA built, or an error if a letter isn't in the alphabet or a state doesn't exist (see [`NFA::from_raw`](./struct.NFA.html#method.from_raw)).
+    pub fn build(self) -> Result<NFA<V>, FromRawError<V>> {
+        let mut transitions: Vec<HashMap<V, Vec<usize>>> = vec![HashMap::new(); self.states];
+        for (source, letter, target) in self.transitions {
+            if source >= self.states {
+                return Err(FromRawError::InvalidTransition(source, letter, target));
+            }
+            let targets = transitions[source].entry(letter).or_default();
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        NFA::from_raw(self.alphabet, self.initials, self.finals, transitions)
+    }
+}
+
 /* IMPLEMENTATION OF NFA */
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
+    /// Returns the alphabet.
+    pub fn alphabet(&self) -> &HashSet<V> {
+        &self.alphabet
+    }
+
+    /// Returns the number of states, which are numbered from `0`.
+    pub fn num_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the initial states.
+    pub fn initials(&self) -> &HashSet<usize> {
+        &self.initials
+    }
+
+    /// Returns the final states.
+    pub fn finals(&self) -> &HashSet<usize> {
+        &self.finals
+    }
+
+    /// Returns the transitions from `state`, each letter being mapped to its targets.
+    ///
+    /// # Panics
+    /// Panics if `state` doesn't exist.
+    pub fn transitions_from(&self, state: usize) -> &HashMap<V, Vec<usize>> {
+        &self.transitions[state]
+    }
+
+    /// Returns the targets of the epsilon transitions from `state`.
+    pub fn epsilon_from(&self, state: usize) -> &[usize] {
+        self.epsilon.get(&state).map_or(&[], Vec::as_slice)
+    }
+
     /// Returns an NFA that accepts a word if and only if this word is accepted by both `self` and `other`.
+    ///
+    /// The alphabet of the result is the union of their alphabets, a word with a letter which isn't in the alphabet of one of them being rejected.
+    /// Both automata are determinized, then their product is built (see [`DFA::intersect`](../dfa/struct.DFA.html#method.intersect)).
     pub fn intersect(self, other: NFA<V>) -> NFA<V> {
-        self.negate().unite(other.negate()).negate().to_nfa()
+        self.to_dfa().intersect(other.to_dfa()).to_nfa()
+    }
+
+    /// Adds the letters to the alphabet, without any transition: for instance, the complement is then taken over the larger alphabet.
+    pub fn extend_alphabet<I: IntoIterator<Item = V>>(&mut self, letters: I) {
+        self.alphabet.extend(letters);
+    }
+
+    /// Returns the states reachable from `states` using only epsilon transitions (including `states` themselves).
+    pub fn epsilon_closure(&self, states: &HashSet<usize>) -> HashSet<usize> {
+        let mut closure = states.clone();
+        if self.epsilon.is_empty() {
+            return closure;
+        }
+
+        let mut stack: Vec<usize> = states.iter().copied().collect();
+        while let Some(s) = stack.pop() {
+            for t in self.epsilon.get(&s).into_iter().flatten() {
+                if closure.insert(*t) {
+                    stack.push(*t);
+                }
+            }
+        }
+        closure
+    }
+
+    /// Adds an epsilon transition from `source` to `target`.
+    pub fn add_epsilon(&mut self, source: usize, target: usize) {
+        let targets = self.epsilon.entry(source).or_default();
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    /// Sorts the targets of each transition and of the epsilon transitions, removing the duplicates.
+    pub fn dedup_transitions(&mut self) {
+        for targets in self
+            .transitions
+            .iter_mut()
+            .flat_map(HashMap::values_mut)
+            .chain(self.epsilon.values_mut())
+        {
+            targets.sort_unstable();
+            targets.dedup();
+        }
+    }
+
+    /// Removes the state `state` with all the transitions from and to it, the following states being renumbered one lower.
+    ///
+    /// # Panics
+    /// Panics if `state` doesn't exist.
+    pub fn remove_state(&mut self, state: usize) {
+        assert!(
+            state < self.transitions.len(),
+            "the state {} doesn't exist (there are {} states)",
+            state,
+            self.transitions.len()
+        );
+
+        let shift = |s: usize| if s > state { s - 1 } else { s };
+        let renumber = |targets: &mut Vec<usize>| {
+            targets.retain(|t| *t != state);
+            targets.iter_mut().for_each(|t| *t = shift(*t));
+        };
+
+        self.transitions.remove(state);
+        for map in &mut self.transitions {
+            map.values_mut().for_each(renumber);
+            map.retain(|_, targets| !targets.is_empty());
+        }
+        self.epsilon = std::mem::take(&mut self.epsilon)
+            .into_iter()
+            .filter(|(s, _)| *s != state)
+            .map(|(s, mut targets)| {
+                renumber(&mut targets);
+                (shift(s), targets)
+            })
+            .filter(|(_, targets)| !targets.is_empty())
+            .collect();
+        self.initials = self
+            .initials
+            .iter()
+            .filter(|s| **s != state)
+            .map(|s| shift(*s))
+            .collect();
+        self.finals = self
+            .finals
+            .iter()
+            .filter(|s| **s != state)
+            .map(|s| shift(*s))
+            .collect();
+    }
+
+    /// Returns an automaton with the same states and without epsilon transitions, accepting the same language.
+    ///
+    /// Each state gets the transitions of the states of its epsilon closure, and is final if its closure contains a final state.
+    pub fn remove_epsilon(mut self) -> NFA<V> {
+        if self.epsilon.is_empty() {
+            return self;
+        }
+
+        let closures: Vec<HashSet<usize>> = (0..self.transitions.len())
+            .map(|s| self.epsilon_closure(&(s..=s).collect()))
+            .collect();
+        let mut transitions: Vec<HashMap<V, Vec<usize>>> = vec![HashMap::new(); closures.len()];
+        for (s, closure) in closures.iter().enumerate() {
+            for c in closure {
+                for (v, ts) in &self.transitions[*c] {
+                    let targets = transitions[s].entry(*v).or_default();
+                    for t in ts {
+                        if !targets.contains(t) {
+                            targets.push(*t);
+                        }
+                    }
+                }
+            }
+            if !closure.is_disjoint(&self.finals) {
+                self.finals.insert(s);
+            }
+        }
+
+        self.transitions = transitions;
+        self.epsilon.clear();
+        self
+    }
+
+    /// Returns a NFA accepting the words of `self`, optionally preceded by a word of `prefix` (i.e. `(prefix)?self`).
+    pub fn optional_prefix(self, prefix: NFA<V>) -> NFA<V> {
+        prefix.repeat(0..=1).concatenate(self)
+    }
+
+    /// Returns a NFA accepting the words of `self`, optionally followed by a word of `suffix` (i.e. `self(suffix)?`).
+    pub fn optional_suffix(self, suffix: NFA<V>) -> NFA<V> {
+        self.concatenate(suffix.repeat(0..=1))
+    }
+
+    /// Returns a NFA accepting the images by `f` of the words of `self`, the letters being mapped one by one.
+    ///
+    /// When two letters have the same image, the transitions with both of them are merged.
+    pub fn map_alphabet<W, F>(self, f: F) -> NFA<W>
+    where
+        W: Eq + Hash + Display + Copy + Clone + Debug + Ord,
+        F: Fn(&V) -> W,
+    {
+        let transitions = self
+            .transitions
+            .into_iter()
+            .map(|map| {
+                let mut mapped: HashMap<W, Vec<usize>> = HashMap::new();
+                for (v, ts) in map {
+                    let targets = mapped.entry(f(&v)).or_default();
+                    for t in ts {
+                        if !targets.contains(&t) {
+                            targets.push(t);
+                        }
+                    }
+                }
+                mapped
+            })
+            .collect();
+
+        NFA {
+            alphabet: self.alphabet.iter().map(&f).collect(),
+            initials: self.initials,
+            finals: self.finals,
+            transitions,
+            epsilon: self.epsilon,
+        }
+    }
+
+    /// Returns a NFA accepting the words of `self` with all the occurrences of `remove` deleted: the transitions with `remove` become epsilon transitions.
+    ///
+    /// The letter is removed from the alphabet.
+    pub fn project(mut self, remove: V) -> NFA<V> {
+        for s in 0..self.transitions.len() {
+            if let Some(targets) = self.transitions[s].remove(&remove) {
+                for t in targets {
+                    self.add_epsilon(s, t);
+                }
+            }
+        }
+        self.alphabet.remove(&remove);
+        self
+    }
+
+    /// Returns an NFA accepting the prefixes of the words accepted by `self`: once trimmed, all the states are made final.
+    pub fn prefix_closure(self) -> NFA<V> {
+        let mut nfa = self.trim();
+        nfa.finals = (0..nfa.transitions.len()).collect();
+        nfa
+    }
+
+    /// Returns an NFA accepting the suffixes of the words accepted by `self`: once trimmed, all the states are made initial.
+    ///
+    /// The empty word is accepted as soon as the language isn't empty, being a suffix of any word.
+    pub fn suffix_closure(self) -> NFA<V> {
+        let mut nfa = self.trim();
+        nfa.initials = (0..nfa.transitions.len()).collect();
+        nfa
+    }
+
+    /// Returns a DFA accepting the words over the alphabet of `self` which `self` rejects.
+    ///
+    /// The result is complete and, unlike [`negate`](../automaton/trait.Automata.html#tymethod.negate), stays deterministic.
+    pub fn complement(&self) -> DFA<V> {
+        self.to_dfa().negate()
     }
 
     /// A contains B if and only if for each `word` w, if B `accepts` w then A `accepts` w.
+    ///
+    /// The words are taken over the union of the alphabets: a word with a letter which isn't in the alphabet of A isn't accepted by A.
     pub fn contains(&self, other: &NFA<V>) -> bool {
-        self.clone().negate().intersect(other.clone()).is_empty()
+        let (mut a, mut b) = (self.clone(), other.clone());
+        share_alphabets(&mut a.alphabet, &mut b.alphabet);
+        a.negate().intersect(b).is_empty()
     }
 
-    fn small_to_dfa<T: Eq + Hash + Copy + BitOr<Output = T>, C: Fn(usize) -> T>(
-        &self,
-        zero: T,
-        shift: C,
-    ) -> DFA<V> {
-        let mut map = HashMap::new();
-        let mut stack = VecDeque::new();
+    /// Returns `Ok(())` if `self` contains `other`, otherwise `Err(w)` where `w` is a shortest word accepted by `other` but not by `self`.
+    pub fn contains_with_witness(&self, other: &NFA<V>) -> Result<(), Vec<V>> {
+        self.to_dfa().contains_with_witness(&other.to_dfa())
+    }
 
-        let mut dfa = DFA::new_empty(&self.alphabet);
+    /// Returns whether the word made of the letters of `it` is accepted, consuming them one by one.
+    ///
+    /// The iteration stops as soon as no state is active anymore, the following letters being left unconsumed.
+    pub fn run_iter<I: IntoIterator<Item = V>>(&self, it: I) -> bool {
+        let mut actuals = self.epsilon_closure(&self.initials);
+        if actuals.is_empty() {
+            return false;
+        }
+        for l in it {
+            let mut next = HashSet::new();
+            for st in &actuals {
+                if let Some(tr) = self.transitions[*st].get(&l) {
+                    next.extend(tr);
+                }
+            }
+            actuals = self.epsilon_closure(&next);
+            if actuals.is_empty() {
+                return false;
+            }
+        }
+        actuals.iter().any(|x| self.finals.contains(x))
+    }
 
-        let i: T = self.initials.iter().fold(zero, |acc, x| acc | shift(*x));
-        if self.initials.iter().any(|x| self.finals.contains(x)) {
-            dfa.finals.insert(0);
+    /// Returns whether the empty word is accepted, i.e. whether a final state is in the epsilon closure of the initial states.
+    pub fn accepts_empty(&self) -> bool {
+        self.epsilon_closure(&self.initials)
+            .iter()
+            .any(|s| self.finals.contains(s))
+    }
+
+    /// Returns whether `v` is accepted, and the sets of active states (closed under the epsilon transitions) before reading `v` and after each of its letters.
+    ///
+    /// If no state is active anymore, the run stops there and the sets computed until then are returned.
+    pub fn run_trace(&self, v: &[V]) -> (bool, Vec<HashSet<usize>>) {
+        let mut actuals = self.epsilon_closure(&self.initials);
+        let mut trace = Vec::with_capacity(v.len() + 1);
+        for l in v {
+            if actuals.is_empty() {
+                return (false, trace);
+            }
+            let mut next = HashSet::new();
+            for st in &actuals {
+                if let Some(tr) = self.transitions[*st].get(l) {
+                    next.extend(tr);
+                }
+            }
+            trace.push(std::mem::replace(&mut actuals, self.epsilon_closure(&next)));
+        }
+        let accepted = actuals.iter().any(|x| self.finals.contains(x));
+        if !actuals.is_empty() {
+            trace.push(actuals);
         }
+        (accepted, trace)
+    }
 
-        map.insert(i, 0);
-        stack.push_back((i, HashSet::from_iter(self.initials.clone().into_iter())));
+    /// Returns the shortest word (the smallest one in the alphabet order among them) accepted by the automaton, or `None` if the language is empty.
+    ///
+    /// The states are visited by a breadth-first search, one length at a time, each state keeping the smallest word reaching it.
+    pub fn shortest_word(&self) -> Option<Vec<V>> {
+        if !self.epsilon.is_empty() {
+            return self.clone().remove_epsilon().shortest_word();
+        }
 
-        while let Some((elem, iter)) = stack.pop_front() {
-            let elem_num = *map.get(&elem).unwrap();
-            for v in &self.alphabet {
-                let mut it = HashSet::new();
-                for state in &iter {
-                    if let Some(transitions) = self.transitions[*state].get(&v) {
-                        for t in transitions {
-                            it.insert(*t);
+        let mut seen = self.initials.clone();
+        let mut layer: HashMap<usize, Vec<V>> =
+            self.initials.iter().map(|s| (*s, Vec::new())).collect();
+        while !layer.is_empty() {
+            if let Some(word) = layer
+                .iter()
+                .filter(|(s, _)| self.finals.contains(s))
+                .map(|(_, w)| w)
+                .min()
+            {
+                return Some(word.clone());
+            }
+
+            let mut next: HashMap<usize, Vec<V>> = HashMap::new();
+            for (s, word) in &layer {
+                for (v, targets) in &self.transitions[*s] {
+                    for t in targets.iter().filter(|t| !seen.contains(t)) {
+                        let mut candidate = word.clone();
+                        candidate.push(*v);
+                        match next.entry(*t) {
+                            Entry::Occupied(mut entry) if candidate < *entry.get() => {
+                                entry.insert(candidate);
+                            }
+                            Entry::Occupied(_) => {}
+                            Entry::Vacant(entry) => {
+                                entry.insert(candidate);
+                            }
                         }
                     }
                 }
-                if it.is_empty() {
-                    continue;
+            }
+            seen.extend(next.keys());
+            layer = next;
+        }
+
+        None
+    }
+
+    /// Returns whether `self` and `other` are bisimilar.
+    ///
+    /// Two states are bisimilar if they are both final or both not final, and if each transition of one of them
+    /// can be matched by a transition of the other with the same letter towards a bisimilar state.
+    /// The automata are bisimilar if each initial state of one of them is bisimilar to an initial state of the other.
+    ///
+    /// This is stronger than `==`, which compares the languages: bisimilar automata accept the same language,
+    /// but two automata accepting the same language may not be bisimilar (e.g. one branching on the first `a` of `ab|ac`, and the deterministic one).
+    pub fn bisimilar(&self, other: &NFA<V>) -> bool {
+        if !self.epsilon.is_empty() || !other.epsilon.is_empty() {
+            return self
+                .clone()
+                .remove_epsilon()
+                .bisimilar(&other.clone().remove_epsilon());
+        }
+
+        // the states of `other` are shifted after those of `self`
+        let shift = self.transitions.len();
+        let successors = |s: usize| -> Vec<(V, usize)> {
+            if s < shift {
+                let map = &self.transitions[s];
+                map.iter()
+                    .flat_map(|(v, ts)| ts.iter().map(move |t| (*v, *t)))
+                    .collect()
+            } else {
+                let map = &other.transitions[s - shift];
+                map.iter()
+                    .flat_map(|(v, ts)| ts.iter().map(move |t| (*v, *t + shift)))
+                    .collect()
+            }
+        };
+        let n = shift + other.transitions.len();
+
+        // partition refinement, starting from the partition between final and non-final states
+        let mut blocks: Vec<usize> = (0..n)
+            .map(|s| {
+                if s < shift {
+                    self.finals.contains(&s) as usize
+                } else {
+                    other.finals.contains(&(s - shift)) as usize
                 }
+            })
+            .collect();
+        let mut count = 0;
+        loop {
+            let mut signatures = HashMap::new();
+            let refined: Vec<usize> = (0..n)
+                .map(|s| {
+                    let moves: BTreeSet<(V, usize)> = successors(s)
+                        .into_iter()
+                        .map(|(v, t)| (v, blocks[t]))
+                        .collect();
+                    let l = signatures.len();
+                    *signatures.entry((blocks[s], moves)).or_insert(l)
+                })
+                .collect();
+            blocks = refined;
+            if signatures.len() == count {
+                break;
+            }
+            count = signatures.len();
+        }
 
-                let other = it.iter().fold(zero, |acc, x| acc | shift(*x));
-                let entry = map.entry(other);
-                let val = entry.or_insert_with(|| {
-                    let l = dfa.transitions.len();
-                    if it.iter().any(|x| self.finals.contains(x)) {
-                        dfa.finals.insert(l);
-                    }
-                    stack.push_back((other, it));
-                    dfa.transitions.push(HashMap::new());
-                    l
-                });
+        let ours: HashSet<usize> = self.initials.iter().map(|s| blocks[*s]).collect();
+        let theirs: HashSet<usize> = other.initials.iter().map(|s| blocks[*s + shift]).collect();
+        ours == theirs
+    }
+
+    /// Returns the subsets of states reached by the subset construction, with the transitions between them, the initial subset being the first one.
+    ///
+    /// At most `max_subsets` subsets are built (in breadth-first order, the letters being sorted): the transitions towards the other subsets are left out.
+    /// Unlike `to_dfa`, the empty subset is kept if it is reached.
+    pub fn subset_graph(
+        &self,
+        max_subsets: usize,
+    ) -> (Vec<HashSet<usize>>, Vec<HashMap<V, usize>>) {
+        let mut subsets = Vec::new();
+        let mut transitions = Vec::new();
+        if max_subsets == 0 {
+            return (subsets, transitions);
+        }
+
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
 
-                dfa.transitions[elem_num].insert(*v, *val);
+        let initials = self.epsilon_closure(&self.initials);
+        let mut map = HashMap::new();
+        map.insert(initials.iter().copied().collect::<BTreeSet<usize>>(), 0);
+        subsets.push(initials);
+        transitions.push(HashMap::new());
+
+        let mut num = 0;
+        while num < subsets.len() {
+            for v in &letters {
+                let mut it = HashSet::new();
+                for state in &subsets[num] {
+                    if let Some(targets) = self.transitions[*state].get(v) {
+                        it.extend(targets);
+                    }
+                }
+                let it: BTreeSet<usize> = self.epsilon_closure(&it).into_iter().collect();
+
+                let l = subsets.len();
+                let target = match map.entry(it) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(_) if l == max_subsets => continue,
+                    Entry::Vacant(entry) => {
+                        subsets.push(entry.key().iter().copied().collect());
+                        transitions.push(HashMap::new());
+                        *entry.insert(l)
+                    }
+                };
+                transitions[num].insert(*v, target);
             }
+            num += 1;
         }
 
-        dfa
+        (subsets, transitions)
+    }
+
+    /// Returns a DFA accepting the same language, built with the subset construction where each subset of states is identified by a key of type `K`.
+    ///
+    /// # Panics
+    /// Panics if a state of `self` can't be inserted in a key of type `K` (e.g. a bitmask which is too small).
+    pub fn to_dfa_with_key<K: SubsetKey>(&self) -> DFA<V> {
+        self.subset_construction::<K>(None)
     }
 
-    fn big_to_dfa(&self) -> DFA<V> {
-        let mut map: HashMap<BTreeSet<usize>, usize> = HashMap::new();
+    /// Returns the DFA built by the subset construction, with the subset of states of `self` represented by each of its states.
+    ///
+    /// The subsets are closed under the epsilon transitions, the one of the initial state `0` being the closure of the initial states.
+    pub fn to_dfa_with_labels(&self) -> (DFA<V>, Vec<BTreeSet<usize>>) {
+        let mut labels = Vec::new();
+        let dfa = self.subset_construction::<BTreeSet<usize>>(Some(&mut labels));
+        (dfa, labels)
+    }
+
+    // the subset construction behind `to_dfa_with_key`, pushing the subset of each state in `labels` if any
+    fn subset_construction<K: SubsetKey>(
+        &self,
+        mut labels: Option<&mut Vec<BTreeSet<usize>>>,
+    ) -> DFA<V> {
+        let mut label = |set: &HashSet<usize>| {
+            if let Some(labels) = labels.as_mut() {
+                labels.push(set.iter().copied().collect());
+            }
+        };
+        let key = |set: &HashSet<usize>| {
+            set.iter().fold(K::empty(), |mut acc, x| {
+                acc.insert(*x);
+                acc
+            })
+        };
+
+        let mut map = HashMap::new();
         let mut stack = VecDeque::new();
 
         let mut dfa = DFA::new_empty(&self.alphabet);
 
-        let initial: BTreeSet<usize> = self.initials.iter().copied().collect();
-        map.insert(initial.clone(), 0);
-        stack.push_back(initial);
-
-        if self.initials.iter().any(|x| self.finals.contains(x)) {
+        let initials = self.epsilon_closure(&self.initials);
+        if initials.iter().any(|x| self.finals.contains(x)) {
             dfa.finals.insert(0);
         }
 
-        while let Some(set) = stack.pop_front() {
-            let num = *map.get(&set).unwrap();
+        label(&initials);
+        map.insert(key(&initials), 0);
+        stack.push_back((0, initials));
+
+        while let Some((num, set)) = stack.pop_front() {
             for v in &self.alphabet {
                 let mut it = HashSet::new();
-                for s in &set {
-                    if let Some(transitions) = self.transitions[*s].get(&v) {
-                        for t in transitions {
-                            it.insert(*t);
-                        }
+                for state in &set {
+                    if let Some(transitions) = self.transitions[*state].get(v) {
+                        it.extend(transitions);
                     }
                 }
+                let it = self.epsilon_closure(&it);
                 if it.is_empty() {
                     continue;
                 }
 
-                let other = it.iter().fold(BTreeSet::new(), |mut acc, x| {
-                    acc.insert(*x);
-                    acc
-                });
-                if !map.contains_key(&other) {
-                    let l = dfa.transitions.len();
-                    map.insert(other.clone(), l);
-                    if it.iter().any(|x| self.finals.contains(x)) {
-                        dfa.finals.insert(l);
+                let target = match map.entry(key(&it)) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => {
+                        let l = dfa.transitions.len();
+                        if it.iter().any(|x| self.finals.contains(x)) {
+                            dfa.finals.insert(l);
+                        }
+                        dfa.transitions.push(HashMap::new());
+                        label(&it);
+                        stack.push_back((l, it));
+                        *entry.insert(l)
                     }
-                    stack.push_back(other.clone());
-                    dfa.transitions.push(HashMap::new());
-                }
-                dfa.transitions[num].insert(*v, *map.get(&other).unwrap());
+                };
+
+                dfa.transitions[num].insert(*v, target);
             }
         }
 
         dfa
     }
 
+    /// Returns whether the automaton is already deterministic: it has exactly one initial state, no epsilon transition,
+    /// and at most one transition from each state with each letter.
+    pub fn is_deterministic(&self) -> bool {
+        self.initials.len() == 1
+            && self.epsilon.values().all(Vec::is_empty)
+            && self
+                .transitions
+                .iter()
+                .all(|map| map.values().all(|targets| targets.len() <= 1))
+    }
+
+    /// Returns the DFA with the same states and transitions, without the subset construction.
+    ///
+    /// The automaton must be deterministic (see [`is_deterministic`](#method.is_deterministic)), which is only checked in debug builds.
+    ///
+    /// # Panics
+    /// Panics if there is no initial state.
+    pub fn into_dfa_unchecked(self) -> DFA<V> {
+        debug_assert!(self.is_deterministic());
+        DFA {
+            alphabet: self.alphabet,
+            initial: *self.initials.iter().next().unwrap(),
+            finals: self.finals,
+            transitions: self
+                .transitions
+                .into_iter()
+                .map(|map| {
+                    map.into_iter()
+                        .filter_map(|(v, targets)| targets.first().map(|t| (v, *t)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the letters with at least one transition from `state`, possibly after some epsilon transitions.
+    pub fn enabled_symbols(&self, state: usize) -> HashSet<V> {
+        if !self.epsilon.is_empty() {
+            return self.clone().remove_epsilon().enabled_symbols(state);
+        }
+        self.transitions[state]
+            .iter()
+            .filter(|(_, targets)| !targets.is_empty())
+            .map(|(v, _)| *v)
+            .collect()
+    }
+
+    /// Returns, for each state, its in-degree and its out-degree, i.e. the number of transitions going to and from that state
+    /// (the epsilon transitions included).
+    pub fn degrees(&self) -> Vec<(usize, usize)> {
+        let mut degrees = vec![(0, 0); self.transitions.len()];
+        for (state, map) in self.transitions.iter().enumerate() {
+            let epsilon = self.epsilon.get(&state).into_iter();
+            for targets in map.values().chain(epsilon) {
+                degrees[state].1 += targets.len();
+                for t in targets {
+                    degrees[*t].0 += 1;
+                }
+            }
+        }
+        degrees
+    }
+
     /// Returns a string containing the dot description of the automaton
     pub fn to_dot(&self) -> String {
-        let mut ret = String::new();
-        ret.push_str("digraph {");
+        let mut ret = Vec::new();
+        self.write_dot(&mut ret).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the dot description of the automaton to `w`, without building it in memory.
+    ///
+    /// The epsilon transitions are the edges labelled `𝜀`.
+    pub fn write_dot<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "digraph {{")?;
 
         if !self.finals.is_empty() {
-            ret.push_str("    node [shape = doublecircle];");
+            write!(w, "    node [shape = doublecircle];")?;
             for e in &self.finals {
-                ret.push_str(&format!(" S_{}", e));
+                write!(w, " S_{}", e)?;
             }
-            ret.push_str(";");
+            write!(w, ";")?;
         }
 
         if !self.initials.is_empty() {
-            ret.push_str("    node [shape = point];");
+            write!(w, "    node [shape = point];")?;
             for e in &self.initials {
-                ret.push_str(&format!(" I_{}", e));
+                write!(w, " I_{}", e)?;
             }
-            ret.push_str(";");
+            write!(w, ";")?;
         }
 
-        ret.push_str("    node [shape = circle];");
+        write!(w, "    node [shape = circle];")?;
         let mut tmp_map = HashMap::new();
         for (i, map) in self.transitions.iter().enumerate() {
-            if map.is_empty() {
-                ret.push_str(&format!("    S_{};", i));
+            let epsilon = self.epsilon.get(&i);
+            if map.is_empty() && epsilon.is_none() {
+                write!(w, "    S_{};", i)?;
             }
             for (k, v) in map {
                 for e in v {
-                    tmp_map.entry(e).or_insert_with(Vec::new).push(k);
+                    tmp_map
+                        .entry(e)
+                        .or_insert_with(Vec::new)
+                        .push(k.to_string());
                 }
             }
-            for (e, v) in tmp_map.drain() {
-                let mut vs = v.into_iter().fold(String::new(), |mut acc, x| {
-                    acc.push_str(&x.to_string());
-                    acc.push_str(", ");
-                    acc
-                });
-                vs.pop();
-                vs.pop();
-                ret.push_str(&format!("    S_{} -> S_{} [label = \"{}\"];", i, e, vs));
+            for e in epsilon.into_iter().flatten() {
+                tmp_map
+                    .entry(e)
+                    .or_insert_with(Vec::new)
+                    .push("𝜀".to_string());
+            }
+            for (e, vs) in tmp_map.drain() {
+                write!(w, "    S_{} -> S_{} [label = \"{}\"];", i, e, vs.join(", "))?;
             }
         }
 
         for e in &self.initials {
-            ret.push_str(&format!("    I_{} -> S_{};", e, e));
+            write!(w, "    I_{} -> S_{};", e, e)?;
+        }
+
+        write!(w, "}}")
+    }
+
+    /// Returns a string containing the transition table of the automaton.
+    pub fn to_table(&self) -> String {
+        let mut ret = Vec::new();
+        self.write_table(&mut ret).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the transition table of the automaton to `w`, without building it in memory.
+    ///
+    /// The columns are separated by tabulations, the first one being the letters in order (and `𝜀` if there are epsilon transitions).
+    /// Each state has its own row, marked by `->` if it is initial and by `*` if it is final, each cell being the set of targets or `-` if it is empty.
+    pub fn write_table<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut letters: Vec<_> = self.alphabet.iter().collect();
+        letters.sort();
+
+        write!(w, "\t")?;
+        for v in &letters {
+            write!(w, "\t{}", v)?;
+        }
+        if !self.epsilon.is_empty() {
+            write!(w, "\t𝜀")?;
+        }
+        writeln!(w)?;
+
+        let write_targets = |w: &mut W, targets: Option<&Vec<usize>>| match targets {
+            Some(targets) if !targets.is_empty() => {
+                let mut targets = targets.clone();
+                targets.sort_unstable();
+                let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+                write!(w, "\t{{{}}}", targets.join(","))
+            }
+            _ => write!(w, "\t-"),
+        };
+        for (s, map) in self.transitions.iter().enumerate() {
+            let initial = if self.initials.contains(&s) { "->" } else { "" };
+            let fin = if self.finals.contains(&s) { "*" } else { "" };
+            write!(w, "{}{}\t{}", initial, fin, s)?;
+            for v in &letters {
+                write_targets(w, map.get(*v))?;
+            }
+            if !self.epsilon.is_empty() {
+                write_targets(w, self.epsilon.get(&s))?;
+            }
+            writeln!(w)?;
         }
 
-        ret.push_str("}");
+        Ok(())
+    }
 
-        ret
+    /// Returns a string containing the Mermaid `stateDiagram-v2` description of the automaton.
+    pub fn to_mermaid(&self) -> String {
+        let mut ret = Vec::new();
+        self.write_mermaid(&mut ret).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    /// Writes the Mermaid `stateDiagram-v2` description of the automaton to `w`, without building it in memory.
+    ///
+    /// The initial states are entered from `[*]` and the final states lead to `[*]`.
+    /// The states, the targets and the letters are sorted so that the output is deterministic.
+    /// The epsilon transitions are labelled `𝜀`, after the letters.
+    pub fn write_mermaid<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "stateDiagram-v2")?;
+
+        let mut initials: Vec<_> = self.initials.iter().collect();
+        initials.sort();
+        for i in initials {
+            writeln!(w, "    [*] --> S{}", i)?;
+        }
+
+        for (i, map) in self.transitions.iter().enumerate() {
+            let epsilon = self.epsilon.get(&i);
+            if map.is_empty() && epsilon.is_none() {
+                writeln!(w, "    S{}", i)?;
+            }
+            let mut letters: BTreeMap<usize, BTreeSet<V>> = BTreeMap::new();
+            for (v, targets) in map {
+                for t in targets {
+                    letters.entry(*t).or_default().insert(*v);
+                }
+            }
+            let epsilon: BTreeSet<usize> = epsilon.into_iter().flatten().copied().collect();
+            for t in &epsilon {
+                letters.entry(*t).or_default();
+            }
+            for (t, vs) in letters {
+                let mut vs: Vec<String> = vs.iter().map(|v| v.to_string()).collect();
+                if epsilon.contains(&t) {
+                    vs.push("𝜀".to_string());
+                }
+                writeln!(w, "    S{} --> S{} : {}", i, t, vs.join(", "))?;
+            }
+        }
+
+        let mut finals: Vec<_> = self.finals.iter().collect();
+        finals.sort();
+        for f in finals {
+            writeln!(w, "    S{} --> [*]", f)?;
+        }
+
+        Ok(())
     }
 
     /// Returns an empty NFA.
@@ -201,9 +924,20 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials: HashSet::new(),
             finals: HashSet::new(),
             transitions: Vec::new(),
+            epsilon: HashMap::new(),
         }
     }
 
+    /// Returns a NFA accepting the words accepted by at least one of the automata, whose states are put one after the other.
+    ///
+    /// The transitions are allocated once for all the states, each automaton being shifted once.
+    pub fn union_all<I: IntoIterator<Item = NFA<V>>>(iter: I) -> NFA<V> {
+        let nfas: Vec<NFA<V>> = iter.into_iter().collect();
+        let mut union = NFA::new_empty(HashSet::new());
+        union.transitions = Vec::with_capacity(nfas.iter().map(|a| a.transitions.len()).sum());
+        nfas.into_iter().fold(union, NFA::unite)
+    }
+
     /// Returns a full NFA.
     pub fn new_full(alphabet: HashSet<V>) -> NFA<V> {
         NFA {
@@ -211,6 +945,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             alphabet,
             initials: (0..=0).collect(),
             finals: (0..=0).collect(),
+            epsilon: HashMap::new(),
         }
     }
 
@@ -230,9 +965,20 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials: (0..=0).collect(),
             finals: (len..=len).collect(),
             transitions,
+            epsilon: HashMap::new(),
         }
     }
 
+    /// Returns a NFA that accepts all words whose length is in `lengths`.
+    ///
+    /// The automaton is a single chain of `max(lengths) + 1` states, the final ones being those whose position is in `lengths`.
+    pub fn new_lengths(alphabet: HashSet<V>, lengths: &[usize]) -> NFA<V> {
+        let len = lengths.iter().copied().max().unwrap_or(0);
+        let mut nfa = NFA::new_length(alphabet, len);
+        nfa.finals = lengths.iter().copied().collect();
+        nfa
+    }
+
     /// Returns a NFA that accepts only the given word.
     pub fn new_matching(alphabet: HashSet<V>, word: &[V]) -> NFA<V> {
         let l = word.len();
@@ -241,6 +987,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials: (0..=0).collect(),
             finals: (l..=l).collect(),
             transitions: repeat(HashMap::new()).take(l + 1).collect(),
+            epsilon: HashMap::new(),
         };
 
         for (i, l) in word.iter().enumerate() {
@@ -257,6 +1004,41 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials: (0..=0).collect(),
             finals: (0..=0).collect(),
             transitions: vec![HashMap::new()],
+            epsilon: HashMap::new(),
+        }
+    }
+
+    /// Returns a NFA that accepts the words containing exactly `counts[v]` occurrences of each letter `v`, in any order.
+    ///
+    /// The alphabet is the set of keys of `counts`. A state is the number of occurrences still expected for each letter,
+    /// so the automaton has the product of the `counts[v] + 1` as number of states.
+    pub fn with_counts(counts: HashMap<V, usize>) -> NFA<V> {
+        let mut letters: Vec<(V, usize)> = counts.into_iter().collect();
+        letters.sort();
+
+        // the state is written in the mixed radix given by the counts, the digit of a letter being the number of occurrences left
+        let mut strides = Vec::with_capacity(letters.len());
+        let mut size = 1;
+        for (_, c) in &letters {
+            strides.push(size);
+            size *= c + 1;
+        }
+
+        let mut transitions: Vec<HashMap<V, Vec<usize>>> = vec![HashMap::new(); size];
+        for (s, map) in transitions.iter_mut().enumerate() {
+            for ((v, c), stride) in letters.iter().zip(&strides) {
+                if (s / stride) % (c + 1) > 0 {
+                    map.insert(*v, vec![s - stride]);
+                }
+            }
+        }
+
+        NFA {
+            alphabet: letters.iter().map(|(v, _)| *v).collect(),
+            initials: (size - 1..size).collect(),
+            finals: (0..=0).collect(),
+            transitions,
+            epsilon: HashMap::new(),
         }
     }
 
@@ -294,10 +1076,164 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials,
             finals,
             transitions,
+            epsilon: HashMap::new(),
         })
     }
 
-    pub fn widening(self, n: usize) -> Self {
+    /// Returns a builder of a NFA over `alphabet`, without any state.
+    pub fn builder(alphabet: HashSet<V>) -> NFABuilder<V> {
+        NFABuilder {
+            alphabet,
+            initials: HashSet::new(),
+            finals: HashSet::new(),
+            states: 0,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Returns an automaton accepting the same language where the initial states with the same outgoing transitions are merged,
+    /// and then the final states with the same incoming transitions are merged.
+    ///
+    /// Two such states accept the same words (respectively are reached by the same words), so merging them doesn't change the language.
+    pub fn dedup_initials_finals(mut self) -> NFA<V> {
+        self = self.remove_epsilon();
+        let mut initials: Vec<usize> = self.initials.iter().copied().collect();
+        initials.sort();
+        let mut outgoing = HashMap::new();
+        let mut representatives: Vec<usize> = (0..self.transitions.len()).collect();
+        for i in initials {
+            let targets: BTreeMap<V, BTreeSet<usize>> = self.transitions[i]
+                .iter()
+                .map(|(v, ts)| (*v, ts.iter().copied().collect()))
+                .collect();
+            let key = (self.finals.contains(&i), targets);
+            representatives[i] = *outgoing.entry(key).or_insert(i);
+        }
+        let nfa = self.merge_states(&representatives);
+
+        let mut incoming: Vec<BTreeSet<(usize, V)>> = vec![BTreeSet::new(); nfa.transitions.len()];
+        for (s, map) in nfa.transitions.iter().enumerate() {
+            for (v, ts) in map {
+                for t in ts {
+                    incoming[*t].insert((s, *v));
+                }
+            }
+        }
+        let mut finals: Vec<usize> = nfa.finals.iter().copied().collect();
+        finals.sort();
+        let mut sources = HashMap::new();
+        let mut representatives: Vec<usize> = (0..nfa.transitions.len()).collect();
+        for f in finals {
+            let key = (nfa.initials.contains(&f), incoming[f].clone());
+            representatives[f] = *sources.entry(key).or_insert(f);
+        }
+        nfa.merge_states(&representatives)
+    }
+
+    // merges each state into `representatives[state]`, which must be its own representative, keeping the order of the remaining states
+    fn merge_states(self, representatives: &[usize]) -> NFA<V> {
+        let mut numbers = vec![0; representatives.len()];
+        let mut l = 0;
+        for (s, r) in representatives.iter().enumerate() {
+            if s == *r {
+                numbers[s] = l;
+                l += 1;
+            }
+        }
+        let number = |s: &usize| numbers[representatives[*s]];
+
+        let mut transitions: Vec<HashMap<V, Vec<usize>>> = vec![HashMap::new(); l];
+        for (s, map) in self.transitions.into_iter().enumerate() {
+            for (v, ts) in map {
+                let targets = transitions[number(&s)].entry(v).or_default();
+                for t in ts {
+                    let t = number(&t);
+                    if !targets.contains(&t) {
+                        targets.push(t);
+                    }
+                }
+            }
+        }
+
+        NFA {
+            alphabet: self.alphabet,
+            initials: self.initials.iter().map(number).collect(),
+            finals: self.finals.iter().map(number).collect(),
+            transitions,
+            epsilon: HashMap::new(),
+        }
+    }
+
+    /// Returns an automaton accepting the same language where the nondeterministic transitions leaving the given `states` are replaced by the subset construction.
+    ///
+    /// Every other state keeps its transitions, and the subsets reached from the given states are determinized as long as they contain more than one state.
+    ///
+    /// This is experimental: it is only useful for automata which are mostly deterministic, and the result may still be nondeterministic.
+    pub fn partial_determinize(mut self, states: &HashSet<usize>) -> NFA<V> {
+        self = self.remove_epsilon();
+        // returns the state equivalent to the union of the states of `set`, creating it if needed
+        fn target<V: Eq + Hash + Display + Copy + Clone + Debug + Ord>(
+            nfa: &mut NFA<V>,
+            set: BTreeSet<usize>,
+            map: &mut HashMap<BTreeSet<usize>, usize>,
+            stack: &mut Vec<(usize, BTreeSet<usize>)>,
+        ) -> usize {
+            if set.len() == 1 {
+                return *set.iter().next().unwrap();
+            }
+            if let Some(t) = map.get(&set) {
+                return *t;
+            }
+
+            let l = nfa.transitions.len();
+            if set.iter().any(|x| nfa.finals.contains(x)) {
+                nfa.finals.insert(l);
+            }
+            nfa.transitions.push(HashMap::new());
+            map.insert(set.clone(), l);
+            stack.push((l, set));
+            l
+        }
+
+        // the subsets are always computed from the original transitions
+        let original = self.transitions.clone();
+        let mut map = HashMap::new();
+        let mut stack = Vec::new();
+
+        let mut sorted: Vec<usize> = states.iter().copied().collect();
+        sorted.sort_unstable();
+        for s in sorted {
+            let letters: Vec<V> = self.transitions[s].keys().copied().collect();
+            for v in letters {
+                let set: BTreeSet<usize> = self.transitions[s][&v].iter().copied().collect();
+                if !set.is_empty() {
+                    let t = target(&mut self, set, &mut map, &mut stack);
+                    self.transitions[s].insert(v, vec![t]);
+                }
+            }
+        }
+
+        while let Some((num, set)) = stack.pop() {
+            let mut next: HashMap<V, BTreeSet<usize>> = HashMap::new();
+            for s in &set {
+                for (v, t) in &original[*s] {
+                    next.entry(*v).or_default().extend(t);
+                }
+            }
+
+            for (v, set) in next {
+                if !set.is_empty() {
+                    let t = target(&mut self, set, &mut map, &mut stack);
+                    self.transitions[num].insert(v, vec![t]);
+                }
+            }
+        }
+
+        self
+    }
+
+    pub fn widening(mut self, n: usize) -> Self {
+        self = self.remove_epsilon();
         let languages: Vec<_> = (0..self.transitions.len())
             .map(|s| self.inputs_accepted_from_state(s, n))
             .collect();
@@ -378,6 +1314,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
             initials,
             finals,
             transitions,
+            epsilon: HashMap::new(),
         }
     }
 
@@ -407,18 +1344,333 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> NFA<V> {
     }
 }
 
+impl NFA<char> {
+    /// Parses the regex pattern and returns an automaton accepting the same language, the alphabet being the set of letters appearing in it.
+    pub fn from_pattern(s: &str) -> Result<NFA<char>, ParseError> {
+        Ok(Regex::from_pattern(s)?.to_nfa())
+    }
+
+    /// Parses the subset of the dot language written by [`write_dot`](#method.write_dot): the `doublecircle` nodes `S_i` are final,
+    /// the edges `I_i -> S_i` from the `point` nodes make `S_i` initial, and the edges `S_i -> S_j [label = "a, b"]` are transitions
+    /// (`𝜀` standing for an epsilon transition).
+    ///
+    /// The alphabet is the set of letters of the labels, so the letters `,`, `"`, `;` and `𝜀` aren't supported.
+    pub fn from_dot(s: &str) -> Result<NFA<char>, String> {
+        let body = s
+            .trim()
+            .strip_prefix("digraph")
+            .map(str::trim_start)
+            .and_then(|s| s.strip_prefix('{'))
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or_else(|| "expected `digraph { ... }`".to_string())?;
+
+        let state = |name: &str, prefix: &str| -> Result<usize, String> {
+            name.strip_prefix(prefix)
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| format!("invalid node `{}`", name))
+        };
+
+        let mut alphabet = HashSet::new();
+        let mut initials = HashSet::new();
+        let mut finals = HashSet::new();
+        let mut arcs = Vec::new();
+        let mut size = 0;
+        let mut shape = "circle";
+        for statement in body.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            if let Some(attributes) = statement.strip_prefix("node") {
+                shape = match attributes.trim() {
+                    "[shape = doublecircle]" => "doublecircle",
+                    "[shape = point]" => "point",
+                    "[shape = circle]" => "circle",
+                    _ => return Err(format!("unknown node attributes `{}`", statement)),
+                };
+            } else if let Some((source, rest)) = statement.split_once("->") {
+                let (target, label) = match rest.split_once('[') {
+                    Some((target, label)) => (target.trim(), Some(label)),
+                    None => (rest.trim(), None),
+                };
+                let target = state(target, "S_")?;
+                size = size.max(target + 1);
+                if state(source.trim(), "I_").is_ok() {
+                    initials.insert(target);
+                    continue;
+                }
+
+                let source = state(source.trim(), "S_")?;
+                size = size.max(source + 1);
+                let label = label
+                    .and_then(|l| l.trim().strip_prefix("label = \""))
+                    .and_then(|l| l.strip_suffix("\"]"))
+                    .ok_or_else(|| format!("missing label in `{}`", statement))?;
+                for letter in label.split(", ") {
+                    let mut chars = letter.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some('𝜀'), None) => arcs.push((source, None, target)),
+                        (Some(c), None) => {
+                            alphabet.insert(c);
+                            arcs.push((source, Some(c), target));
+                        }
+                        _ => return Err(format!("invalid letter `{}`", letter)),
+                    }
+                }
+            } else {
+                for name in statement.split_whitespace() {
+                    match shape {
+                        "point" => {
+                            state(name, "I_")?;
+                        }
+                        _ => {
+                            let s = state(name, "S_")?;
+                            size = size.max(s + 1);
+                            if shape == "doublecircle" {
+                                finals.insert(s);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut nfa = NFA {
+            alphabet,
+            initials,
+            finals,
+            transitions: vec![HashMap::new(); size],
+            epsilon: HashMap::new(),
+        };
+        for (source, letter, target) in arcs {
+            match letter {
+                Some(letter) => {
+                    let targets = nfa.transitions[source].entry(letter).or_default();
+                    if !targets.contains(&target) {
+                        targets.push(target);
+                    }
+                }
+                None => nfa.add_epsilon(source, target),
+            }
+        }
+
+        Ok(nfa)
+    }
+
+    /// Returns whether the automaton accepts the characters of `s`, without collecting them first.
+    pub fn run_str(&self, s: &str) -> bool {
+        let mut actuals = self.epsilon_closure(&self.initials);
+        let mut next = HashSet::new();
+
+        for l in s.chars() {
+            for st in &actuals {
+                if let Some(tr) = self.transitions[*st].get(&l) {
+                    next.extend(tr);
+                }
+            }
+
+            next = self.epsilon_closure(&next);
+            std::mem::swap(&mut actuals, &mut next);
+            if actuals.is_empty() {
+                return false;
+            }
+            next.clear();
+        }
+
+        actuals.iter().any(|x| self.finals.contains(x))
+    }
+}
+
+fn parse_state(s: &str, line: usize) -> Result<usize, String> {
+    s.parse()
+        .map_err(|_| format!("line {}: invalid state `{}`", line, s))
+}
+
+fn parse_states(s: &str, line: usize) -> Result<HashSet<usize>, String> {
+    s.split_whitespace().map(|x| parse_state(x, line)).collect()
+}
+
+/// Parses the textual format written by `Display`: a line `initials: ` and a line `finals: ` followed by the lists of states,
+/// and one line `source letter target,target...` for each transition, the letter `𝜀` standing for the epsilon transitions.
+/// Blank lines and lines starting with `#` are ignored.
+///
+/// The alphabet is the set of letters of the transitions, and the number of states is one more than the biggest state appearing.
+impl FromStr for NFA<char> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<NFA<char>, String> {
+        let mut initials = None;
+        let mut finals = None;
+        let mut arcs = Vec::new();
+        let mut size = 0;
+
+        for (i, line) in s.lines().enumerate() {
+            let n = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("initials:") {
+                if initials.is_some() {
+                    return Err(format!("line {}: duplicate `initials:` header", n));
+                }
+                initials = Some(parse_states(rest, n)?);
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix("finals:") {
+                if finals.is_some() {
+                    return Err(format!("line {}: duplicate `finals:` header", n));
+                }
+                finals = Some(parse_states(rest, n)?);
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(format!(
+                    "line {}: expected `source letter targets`, found `{}`",
+                    n, line
+                ));
+            }
+            let source = parse_state(fields[0], n)?;
+            let mut letter = fields[1].chars();
+            let letter = match (letter.next(), letter.next()) {
+                (Some(c), None) => c,
+                _ => return Err(format!("line {}: invalid letter `{}`", n, fields[1])),
+            };
+            let targets = fields[2]
+                .split(',')
+                .map(|x| parse_state(x, n))
+                .collect::<Result<Vec<usize>, String>>()?;
+            size = targets
+                .iter()
+                .fold(size.max(source + 1), |m, t| m.max(t + 1));
+            arcs.push((source, letter, targets));
+        }
+
+        let initials = initials.unwrap_or_default();
+        let finals = finals.unwrap_or_default();
+        size = initials
+            .iter()
+            .chain(&finals)
+            .fold(size, |m, s| m.max(s + 1));
+
+        let mut alphabet = HashSet::new();
+        let mut transitions: Vec<HashMap<char, Vec<usize>>> = vec![HashMap::new(); size];
+        let mut epsilon: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (source, letter, targets) in arcs {
+            let vec = if letter == '𝜀' {
+                epsilon.entry(source).or_default()
+            } else {
+                alphabet.insert(letter);
+                transitions[source].entry(letter).or_default()
+            };
+            for t in targets {
+                if !vec.contains(&t) {
+                    vec.push(t);
+                }
+            }
+        }
+
+        Ok(NFA {
+            alphabet,
+            initials,
+            finals,
+            transitions,
+            epsilon,
+        })
+    }
+}
+
+// the fields of a serialized NFA, before their validation
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "NFA")]
+struct RawNFA<V: Eq + Hash> {
+    alphabet: HashSet<V>,
+    initials: HashSet<usize>,
+    finals: HashSet<usize>,
+    transitions: Vec<HashMap<V, Vec<usize>>>,
+    epsilon: HashMap<usize, Vec<usize>>,
+}
+
+/// The deserialized automaton is validated by [`from_raw`](./struct.NFA.html#method.from_raw), whose error is returned if a state or a letter is invalid
+/// (including the states of the epsilon transitions).
+#[cfg(feature = "serde")]
+impl<'de, V> serde::Deserialize<'de> for NFA<V>
+where
+    V: Eq + Hash + Display + Copy + Clone + Debug + Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawNFA::deserialize(deserializer)?;
+        let mut nfa = NFA::from_raw(raw.alphabet, raw.initials, raw.finals, raw.transitions)
+            .map_err(serde::de::Error::custom)?;
+        let len = nfa.transitions.len();
+        for (s, targets) in &raw.epsilon {
+            for t in targets {
+                if *s >= len || *t >= len {
+                    return Err(serde::de::Error::custom(
+                        FromRawError::<V>::InvalidEpsilonTransition(*s, *t),
+                    ));
+                }
+            }
+        }
+        // a state without epsilon transitions has no entry
+        nfa.epsilon = raw
+            .epsilon
+            .into_iter()
+            .filter(|(_, targets)| !targets.is_empty())
+            .collect();
+        Ok(nfa)
+    }
+}
+
+/// Writes the automaton in the textual format read by `FromStr`, the states and the letters being sorted.
+///
+/// The epsilon transitions of a state are written after its other transitions, with the letter `𝜀`.
+/// The letters without any transition aren't written, so they are lost by a round trip.
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Display for NFA<V> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut initials: Vec<usize> = self.initials.iter().copied().collect();
+        initials.sort_unstable();
+        let mut finals: Vec<usize> = self.finals.iter().copied().collect();
+        finals.sort_unstable();
+        let join = |v: &[usize], sep: &str| {
+            v.iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(sep)
+        };
+
+        writeln!(f, "initials: {}", join(&initials, " "))?;
+        writeln!(f, "finals: {}", join(&finals, " "))?;
+        for (s, map) in self.transitions.iter().enumerate() {
+            let mut letters: Vec<&V> = map.keys().collect();
+            letters.sort();
+            for v in letters {
+                let mut targets = map[v].clone();
+                targets.sort_unstable();
+                writeln!(f, "{} {} {}", s, v, join(&targets, ","))?;
+            }
+            if let Some(targets) = self.epsilon.get(&s).filter(|t| !t.is_empty()) {
+                let mut targets = targets.clone();
+                targets.sort_unstable();
+                writeln!(f, "{} 𝜀 {}", s, join(&targets, ","))?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToDfa<V> for NFA<V> {
     fn to_dfa(&self) -> DFA<V> {
         if self.is_empty() {
             DFA::new_empty(&self.alphabet)
-        } else if self.transitions.len() < 32 {
-            self.small_to_dfa(0 as u32, |x| 1 << x)
-        } else if self.transitions.len() < 64 {
-            self.small_to_dfa(0 as u64, |x| 1 << x)
-        } else if self.transitions.len() < 128 {
-            self.small_to_dfa(0 as u128, |x| 1 << x)
+        } else if self.transitions.len() <= 32 {
+            self.to_dfa_with_key::<u32>()
+        } else if self.transitions.len() <= 64 {
+            self.to_dfa_with_key::<u64>()
+        } else if self.transitions.len() <= 128 {
+            self.to_dfa_with_key::<u128>()
         } else {
-            self.big_to_dfa()
+            self.to_dfa_with_key::<BTreeSet<usize>>()
         }
     }
 }
@@ -452,6 +1704,11 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToRegex<V> for NFA<V>
                 }
             }
         }
+        for (i, v) in &self.epsilon {
+            for &j in v {
+                mat1[*i][j] += Operations::Epsilon;
+            }
+        }
 
         for k in 0..n {
             for i in 0..n {
@@ -485,7 +1742,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
             return false;
         }
 
-        let mut actuals = self.initials.clone();
+        let mut actuals = self.epsilon_closure(&self.initials);
         let mut next = HashSet::new();
 
         for l in v {
@@ -497,6 +1754,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
                 }
             }
 
+            next = self.epsilon_closure(&next);
             std::mem::swap(&mut actuals, &mut next);
             if actuals.is_empty() {
                 return false;
@@ -508,6 +1766,9 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
     }
 
     fn is_complete(&self) -> bool {
+        if !self.epsilon.is_empty() {
+            return self.clone().remove_epsilon().is_complete();
+        }
         if self.initials.is_empty() {
             return false;
         }
@@ -529,7 +1790,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
         let mut acc: HashSet<usize> = self.initials.clone().into_iter().collect();
         let mut stack: Vec<usize> = self.initials.iter().cloned().collect();
         while let Some(e) = stack.pop() {
-            for v in self.transitions[e].values() {
+            for v in self.transitions[e].values().chain(self.epsilon.get(&e)) {
                 for t in v {
                     if !acc.contains(t) {
                         acc.insert(*t);
@@ -558,7 +1819,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
         let mut stack: Vec<usize> = self.initials.clone().into_iter().collect();
 
         while let Some(e) = stack.pop() {
-            for v in self.transitions[e].values() {
+            for v in self.transitions[e].values().chain(self.epsilon.get(&e)) {
                 for t in v {
                     if self.finals.contains(t) {
                         return false;
@@ -574,6 +1835,9 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
     }
 
     fn is_full(&self) -> bool {
+        if !self.epsilon.is_empty() {
+            return self.clone().remove_epsilon().is_full();
+        }
         if self.initials.is_disjoint(&self.finals) {
             return false;
         }
@@ -602,6 +1866,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
     }
 
     fn complete(mut self) -> NFA<V> {
+        self = self.remove_epsilon();
         if self.is_complete() {
             return self;
         }
@@ -625,6 +1890,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
     }
 
     fn make_reachable(mut self) -> NFA<V> {
+        self = self.remove_epsilon();
         let mut acc: HashSet<usize> = self.initials.clone().into_iter().collect();
         let mut stack: Vec<usize> = self.initials.iter().cloned().collect();
         while let Some(e) = stack.pop() {
@@ -690,7 +1956,15 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Automata<V> for NFA<V>
             }
         }
 
+        let mut epsilon: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, v) in &self.epsilon {
+            for e in v {
+                epsilon.entry(*e).or_default().push(*i);
+            }
+        }
+
         self.transitions = transitions;
+        self.epsilon = epsilon;
         std::mem::swap(&mut self.initials, &mut self.finals);
         self
     }
@@ -703,6 +1977,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Buildable<V> for NFA<V
             initials,
             finals,
             transitions,
+            epsilon,
         } = other;
 
         let l = self.transitions.len();
@@ -711,6 +1986,7 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Buildable<V> for NFA<V
         append_shift_hashset(&mut self.initials, initials, l);
         append_shift_hashset(&mut self.finals, finals, l);
         append_shift_transitions(&mut self.transitions, transitions);
+        append_shift_epsilon(&mut self.epsilon, epsilon, l);
 
         self
     }
@@ -723,67 +1999,33 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Buildable<V> for NFA<V
             initials,
             finals,
             mut transitions,
+            epsilon,
         } = other;
 
         append_hashset(&mut self.alphabet, alphabet);
-
-        for e in &initials {
-            for (v, t) in &mut transitions[e - l] {
-                // e - l because of the shift above
-                for f in &self.finals {
-                    self.transitions[*f]
-                        .entry(*v)
-                        .or_insert_with(Vec::new)
-                        .append(&mut t.clone());
-                }
+        self.epsilon.extend(epsilon);
+        for f in std::mem::replace(&mut self.finals, finals) {
+            for i in &initials {
+                self.add_epsilon(f, *i);
             }
         }
-
-        if finals.is_disjoint(&initials) {
-            self.finals = finals;
-        } else {
-            append_hashset(&mut self.finals, finals);
-        }
         self.transitions.append(&mut transitions);
+        self.dedup_transitions();
 
         self
     }
 
     fn kleene(mut self) -> NFA<V> {
         let l = self.transitions.len();
-        let mut map = HashMap::new();
+        self.transitions.push(HashMap::new());
 
-        for i in &self.initials {
-            for (k, v) in &self.transitions[*i] {
-                let set = &mut map.entry(*k).or_insert_with(HashSet::new);
-                for x in v {
-                    set.insert(*x);
-                }
-            }
+        for i in std::mem::replace(&mut self.initials, (l..=l).collect()) {
+            self.add_epsilon(l, i);
         }
-
-        for i in &self.finals {
-            for (k, v) in &map {
-                let mut set: HashSet<usize> = self.transitions[*i]
-                    .entry(*k)
-                    .or_insert_with(Vec::new)
-                    .drain(..)
-                    .collect();
-                for x in v {
-                    set.insert(*x);
-                }
-                self.transitions[*i].insert(*k, set.into_iter().collect());
-            }
+        for f in std::mem::replace(&mut self.finals, (l..=l).collect()) {
+            self.add_epsilon(f, l);
         }
-
-        self.transitions.push(
-            map.into_iter()
-                .map(|(k, v)| (k, v.into_iter().collect()))
-                .collect(),
-        );
-        self.initials.clear();
-        self.initials.insert(l);
-        self.finals.insert(l);
+        self.dedup_transitions();
 
         self
     }
@@ -840,6 +2082,8 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Buildable<V> for NFA<V
     }
 }
 
+/// The equality of the languages, the words being taken over the union of the alphabets (see [`contains`](./struct.NFA.html#method.contains)):
+/// two automata over different alphabets are equal if they accept the same words.
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialEq<NFA<V>> for NFA<V> {
     fn eq(&self, other: &NFA<V>) -> bool {
         self.le(other) && self.ge(other)
@@ -926,7 +2170,8 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Not for NFA<V> {
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Sub for NFA<V> {
     type Output = Self;
 
-    fn sub(self, other: NFA<V>) -> NFA<V> {
+    fn sub(mut self, mut other: NFA<V>) -> NFA<V> {
+        share_alphabets(&mut self.alphabet, &mut other.alphabet);
         self.intersect(other.negate())
     }
 }
@@ -0,0 +1,424 @@
+/* REGEX PARSER */
+
+use crate::regex::Operations;
+use std::{
+    collections::{BTreeSet, HashSet},
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+use Token::*;
+
+/// The tokens of the regex syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Letter(char),
+    /// A letter escaped by a backslash, e.g. `\*`.
+    Escaped(char),
+    Union,
+    LeftParenthesis,
+    RightParenthesis,
+    Kleene,
+    Plus,
+    Question,
+    /// The `{` opening a bounded repetition.
+    LeftBrace,
+    /// A character of the bounds of a repetition (a digit or the comma), which isn't a letter of the regex.
+    Bound(char),
+    RightBrace,
+    /// The dot, with whether it matches the newline.
+    Dot(bool),
+    Epsilon,
+    Empty,
+    /// The `[` (or `[^` if negated) opening a character class.
+    ClassStart(bool),
+    /// The `-` of a range inside a character class.
+    Range,
+    ClassEnd,
+}
+
+/// An error while parsing a regex, the positions being byte offsets in the parsed string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token which can't appear at this position (e.g. a `|` after an opening parenthesis).
+    UnexpectedToken { found: Token, pos: usize },
+    /// A parenthesis which isn't closed, or which closes nothing.
+    UnbalancedParen { pos: usize },
+    /// A quantifier which doesn't follow anything to repeat.
+    UnexpectedQuantifier { pos: usize },
+    /// A bounded repetition whose bounds aren't numbers, or whose minimum is greater than its maximum.
+    InvalidRepetition { pos: usize },
+    /// The regex ends where something was expected (e.g. after a `|`).
+    UnexpectedEnd,
+    /// A letter which isn't in the alphabet given to [`Regex::parse_with_alphabet`](../regex/struct.Regex.html#method.parse_with_alphabet).
+    UnknownLetter(char),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ParseError::UnexpectedToken { found, pos } => {
+                write!(f, "unexpected token {:?} at position {}", found, pos)
+            }
+            ParseError::UnbalancedParen { pos } => {
+                write!(f, "unbalanced parenthesis at position {}", pos)
+            }
+            ParseError::UnexpectedQuantifier { pos } => {
+                write!(f, "unexpected quantifier at position {}", pos)
+            }
+            ParseError::InvalidRepetition { pos } => {
+                write!(f, "invalid repetition at position {}", pos)
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of regex"),
+            ParseError::UnknownLetter(c) => write!(f, "the letter {} is not in the alphabet", c),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Splits the string into tokens, every character which isn't an operator being a letter, each token coming with its byte offset.
+///
+/// By default `.` matches any letter, including the newline. The inline flag `(?-s)` makes the following dots exclude the newline,
+/// until the inline flag `(?s)` restores the default (whatever the parentheses between them).
+///
+/// Inside a character class every character is a letter, except the closing `]` and a `-` between two letters.
+///
+/// Inside the braces of a bounded repetition every character is a bound character, except the closing `}`.
+///
+/// A backslash makes the following character a letter, even inside a character class. A backslash ending the string is a letter itself.
+pub(crate) fn tokens(s: &str) -> Vec<(Token, usize)> {
+    let (offsets, chars): (Vec<usize>, Vec<char>) = s.char_indices().unzip();
+    let mut tokens = Vec::with_capacity(chars.len());
+    let mut dot_all = true;
+    let mut in_class = false;
+    let mut in_braces = false;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            let c = chars[i + 1];
+            tokens.push((if in_class { Letter(c) } else { Escaped(c) }, offsets[i]));
+            i += 2;
+            continue;
+        }
+        if in_class {
+            let token = match chars[i] {
+                ']' => {
+                    in_class = false;
+                    ClassEnd
+                }
+                '-' if matches!(tokens.last(), Some((Letter(_), _)))
+                    && !matches!(chars.get(i + 1), None | Some(']')) =>
+                {
+                    Range
+                }
+                c => Letter(c),
+            };
+            tokens.push((token, offsets[i]));
+            i += 1;
+            continue;
+        }
+        if in_braces {
+            let token = match chars[i] {
+                '}' => {
+                    in_braces = false;
+                    RightBrace
+                }
+                c => Bound(c),
+            };
+            tokens.push((token, offsets[i]));
+            i += 1;
+            continue;
+        }
+        if chars[i] == '[' {
+            in_class = true;
+            let negated = chars.get(i + 1) == Some(&'^');
+            tokens.push((ClassStart(negated), offsets[i]));
+            i += if negated { 2 } else { 1 };
+            continue;
+        }
+        if chars[i..].starts_with(&['(', '?', 's', ')']) {
+            dot_all = true;
+            i += 4;
+            continue;
+        }
+        if chars[i..].starts_with(&['(', '?', '-', 's', ')']) {
+            dot_all = false;
+            i += 5;
+            continue;
+        }
+
+        let token = match chars[i] {
+            '|' => Union,
+            '(' => LeftParenthesis,
+            ')' => RightParenthesis,
+            '*' => Kleene,
+            '+' => Plus,
+            '?' => Question,
+            '{' => {
+                in_braces = true;
+                LeftBrace
+            }
+            '}' => RightBrace,
+            '.' => Dot(dot_all),
+            '𝜀' => Epsilon,
+            '∅' => Empty,
+            c => Letter(c),
+        };
+        tokens.push((token, offsets[i]));
+        i += 1;
+    }
+    tokens
+}
+
+/// Returns the letters appearing in the tokens, including all those of the ranges of the character classes.
+pub(crate) fn letters(tokens: &[(Token, usize)]) -> HashSet<char> {
+    let mut letters = HashSet::new();
+    for (i, (token, _)) in tokens.iter().enumerate() {
+        match token {
+            Letter(c) | Escaped(c) => {
+                letters.insert(*c);
+            }
+            Range => {
+                if let (Some((Letter(a), _)), Some((Letter(b), _))) =
+                    (tokens.get(i.wrapping_sub(1)), tokens.get(i + 1))
+                {
+                    letters.extend(*a..=*b);
+                }
+            }
+            _ => {}
+        }
+    }
+    letters
+}
+
+/// Parses the whole list of tokens, the empty list being the regex matching only the empty word.
+///
+/// The negated character classes match the letters of `alphabet` which they don't list.
+pub(crate) fn parse(
+    tokens: &[(Token, usize)],
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    if tokens.is_empty() {
+        return Ok(Operations::Epsilon);
+    }
+
+    let mut pos = 0;
+    let regex = read_union(tokens, &mut pos, alphabet)?;
+    match tokens.get(pos) {
+        None => Ok(regex),
+        Some((RightParenthesis, pos)) => Err(ParseError::UnbalancedParen { pos: *pos }),
+        Some((t, pos)) => Err(ParseError::UnexpectedToken {
+            found: *t,
+            pos: *pos,
+        }),
+    }
+}
+
+/// union := concat ('|' concat)*
+fn read_union(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    let mut regex = read_concat(tokens, pos, alphabet)?;
+    while let Some((Union, _)) = tokens.get(*pos) {
+        *pos += 1;
+        regex += read_concat(tokens, pos, alphabet)?;
+    }
+    Ok(regex)
+}
+
+/// concat := quantif quantif*
+fn read_concat(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    let mut regex = read_quantif(tokens, pos, alphabet)?;
+    while let Some((t, _)) = tokens.get(*pos) {
+        match t {
+            Union | RightParenthesis => break,
+            _ => regex = regex * read_quantif(tokens, pos, alphabet)?,
+        }
+    }
+    Ok(regex)
+}
+
+/// quantif := atom ('*' | '+' | '?' | bounds)*
+fn read_quantif(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    let mut regex = read_atom(tokens, pos, alphabet)?;
+    while let Some((t, _)) = tokens.get(*pos) {
+        regex = match t {
+            Kleene => Operations::Repeat(Box::new(regex), 0, None),
+            Plus => Operations::Repeat(Box::new(regex), 1, None),
+            Question => Operations::Repeat(Box::new(regex), 0, Some(1)),
+            LeftBrace => {
+                let (min, max) = read_bounds(tokens, pos)?;
+                regex = Operations::Repeat(Box::new(regex), min, max);
+                continue;
+            }
+            _ => break,
+        };
+        *pos += 1;
+    }
+    Ok(regex)
+}
+
+/// bounds := '{' number '}' | '{' number ',' '}' | '{' number ',' number '}'
+fn read_bounds(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+) -> Result<(usize, Option<usize>), ParseError> {
+    let start = tokens[*pos].1;
+    *pos += 1;
+    let mut content = String::new();
+    loop {
+        match tokens.get(*pos) {
+            Some((RightBrace, _)) => break,
+            Some((Bound(c), _)) => content.push(*c),
+            Some((found, offset)) => {
+                return Err(ParseError::UnexpectedToken {
+                    found: *found,
+                    pos: *offset,
+                })
+            }
+            None => return Err(ParseError::UnexpectedEnd),
+        }
+        *pos += 1;
+    }
+    *pos += 1;
+
+    let number = |s: &str| {
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            s.parse::<usize>().ok()
+        } else {
+            None
+        }
+    };
+    let invalid = ParseError::InvalidRepetition { pos: start };
+    let (min, max) = match content.split_once(',') {
+        None => {
+            let n = number(&content).ok_or(invalid)?;
+            (n, Some(n))
+        }
+        Some((min, "")) => (number(min).ok_or(invalid)?, None),
+        Some((min, max)) => (
+            number(min).ok_or(invalid)?,
+            Some(number(max).ok_or(invalid)?),
+        ),
+    };
+    match max {
+        Some(max) if max < min => Err(invalid),
+        _ => Ok((min, max)),
+    }
+}
+
+/// atom := paren | class | letter
+fn read_atom(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    match tokens.get(*pos) {
+        Some((LeftParenthesis, _)) => read_paren(tokens, pos, alphabet),
+        Some((ClassStart(negated), _)) => read_class(tokens, pos, *negated, alphabet),
+        _ => read_letter(tokens, pos),
+    }
+}
+
+/// paren := '(' union ')'
+fn read_paren(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    let start = tokens[*pos].1;
+    *pos += 1;
+    let regex = read_union(tokens, pos, alphabet)?;
+    match tokens.get(*pos) {
+        Some((RightParenthesis, _)) => {
+            *pos += 1;
+            Ok(regex)
+        }
+        _ => Err(ParseError::UnbalancedParen { pos: start }),
+    }
+}
+
+/// class := '[' '^'? (letter | letter '-' letter)* ']'
+fn read_class(
+    tokens: &[(Token, usize)],
+    pos: &mut usize,
+    negated: bool,
+    alphabet: &HashSet<char>,
+) -> Result<Operations<char>, ParseError> {
+    *pos += 1;
+    let mut set = BTreeSet::new();
+    loop {
+        match tokens.get(*pos) {
+            Some((ClassEnd, _)) => break,
+            Some((Letter(a), _)) => match (tokens.get(*pos + 1), tokens.get(*pos + 2)) {
+                (Some((Range, offset)), Some((Letter(b), _))) => {
+                    if a > b {
+                        return Err(ParseError::UnexpectedToken {
+                            found: Range,
+                            pos: *offset,
+                        });
+                    }
+                    set.extend(*a..=*b);
+                    *pos += 3;
+                }
+                _ => {
+                    set.insert(*a);
+                    *pos += 1;
+                }
+            },
+            Some((found, offset)) => {
+                return Err(ParseError::UnexpectedToken {
+                    found: *found,
+                    pos: *offset,
+                })
+            }
+            None => return Err(ParseError::UnexpectedEnd),
+        }
+    }
+    *pos += 1;
+
+    let mut letters: Vec<char> = if negated {
+        alphabet
+            .iter()
+            .filter(|c| !set.contains(c))
+            .copied()
+            .collect()
+    } else {
+        set.into_iter().collect()
+    };
+    letters.sort_unstable();
+    Ok(letters
+        .into_iter()
+        .fold(Operations::Empty, |acc, c| acc + Operations::Letter(c)))
+}
+
+/// letter := any letter | '\\' any character | '.' | '𝜀' | '∅'
+fn read_letter(tokens: &[(Token, usize)], pos: &mut usize) -> Result<Operations<char>, ParseError> {
+    let (token, offset) = match tokens.get(*pos) {
+        Some((t, offset)) => (*t, *offset),
+        None => return Err(ParseError::UnexpectedEnd),
+    };
+    let regex = match token {
+        Letter(c) | Escaped(c) => Operations::Letter(c),
+        Dot(true) => Operations::Dot(None),
+        Dot(false) => Operations::Dot(Some('\n')),
+        Epsilon => Operations::Epsilon,
+        Empty => Operations::Empty,
+        Kleene | Plus | Question | LeftBrace => {
+            return Err(ParseError::UnexpectedQuantifier { pos: offset })
+        }
+        found => return Err(ParseError::UnexpectedToken { found, pos: offset }),
+    };
+    *pos += 1;
+    Ok(regex)
+}
@@ -1,13 +1,15 @@
+pub use crate::parser::{ParseError, Token};
 use crate::{
     automaton::{Automaton, Buildable},
     dfa::{ToDfa, DFA},
     nfa::{ToNfa, NFA},
+    parser::{letters, parse, tokens},
     utils::*,
 };
 use std::{
     cmp::{Ordering, Ordering::*},
-    collections::{BTreeSet, HashSet, VecDeque},
-    fmt::{Debug, Display},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
     hash::Hash,
     ops::{Add, AddAssign, Bound::*, Mul, RangeBounds},
     str::FromStr,
@@ -29,9 +31,13 @@ pub(crate) enum Operations<V: Eq + Hash + Display + Copy + Clone + Debug> {
     Letter(V),
     Epsilon,
     Empty,
-    Dot,
+    /// Any letter of the alphabet, except the given one if any.
+    Dot(Option<V>),
 }
 
+// the derivatives of a regex, each one with a prefix it is the derivative by
+type Derivatives<V> = Vec<(Vec<V>, Operations<V>)>;
+
 /// An interface for structs that can be converted into a Regex.
 pub trait ToRegex<V: Eq + Hash + Display + Copy + Clone + Debug> {
     fn to_regex(&self) -> Regex<V>;
@@ -65,10 +71,194 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Regex<V> {
         }
     }
 
+    /// Returns the number of characters of the string representation of the regex, without building it.
+    pub(crate) fn printed_len(&self) -> usize {
+        self.regex.printed_len(&self.alphabet).0
+    }
+
     /// A contains B if and only if for each `word` w, if B `accepts` w then A `accepts` w.
     pub fn contains(&self, other: &Regex<V>) -> bool {
         self.to_nfa().contains(&other.to_nfa())
     }
+
+    /// Returns the distinct [Brzozowski derivatives](https://en.wikipedia.org/wiki/Brzozowski_derivative) of the regex, each one with the shortest prefix (the smallest one in the alphabet order) it is the derivative by.
+    ///
+    /// The derivatives are compared after normalization of unions and concatenations, so the list is always finite, and the first one is the regex itself.
+    pub fn derivatives(&self) -> Vec<(Vec<V>, Regex<V>)> {
+        self.derivative_automaton()
+            .0
+            .into_iter()
+            .map(|(prefix, regex)| {
+                (
+                    prefix,
+                    Regex {
+                        alphabet: self.alphabet.clone(),
+                        regex,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Returns a complete DFA whose states are the [`derivatives`](#method.derivatives) of the regex, as an alternative to the conversion through a NFA.
+    pub fn to_dfa_derivatives(&self) -> DFA<V> {
+        self.derivative_automaton().1
+    }
+
+    fn derivative_automaton(&self) -> (Derivatives<V>, DFA<V>) {
+        let mut letters: Vec<V> = self.alphabet.iter().copied().collect();
+        letters.sort();
+
+        let mut map = HashMap::new();
+        let mut derivatives = vec![(Vec::new(), self.regex.clone())];
+        let mut transitions = vec![HashMap::new()];
+        map.insert(self.regex.clone(), 0);
+
+        let mut actual = 0;
+        while actual < derivatives.len() {
+            for v in &letters {
+                let derivative = derivatives[actual].1.derivative(v, &self.alphabet);
+                let l = derivatives.len();
+                let num = *map.entry(derivative.clone()).or_insert(l);
+                if num == l {
+                    let mut prefix = derivatives[actual].0.clone();
+                    prefix.push(*v);
+                    derivatives.push((prefix, derivative));
+                    transitions.push(HashMap::new());
+                }
+                transitions[actual].insert(*v, num);
+            }
+            actual += 1;
+        }
+
+        let dfa = DFA {
+            alphabet: self.alphabet.clone(),
+            initial: 0,
+            finals: (0..derivatives.len())
+                .filter(|x| derivatives[*x].1.nullable())
+                .collect(),
+            transitions,
+        };
+
+        (derivatives, dfa)
+    }
+}
+
+impl Regex<char> {
+    /// Parses the regex using the given alphabet, returning an error if it contains a letter which isn't in the alphabet.
+    pub fn parse_with_alphabet(
+        alphabet: HashSet<char>,
+        s: &str,
+    ) -> Result<Regex<char>, ParseError> {
+        let regex = parse(&tokens(s), &alphabet)?;
+        if let Some(c) = regex.alphabet().difference(&alphabet).next() {
+            return Err(ParseError::UnknownLetter(*c));
+        }
+
+        Ok(Regex { alphabet, regex })
+    }
+
+    /// Parses the regex pattern, the alphabet being the set of letters appearing in it (those of the ranges included).
+    ///
+    /// By default `.` matches any letter of the alphabet, including `'\n'`: the inline flag `(?-s)` makes the following dots exclude `'\n'`, and `(?s)` restores the default.
+    ///
+    /// The character classes `[abc]`, `[a-z]` and `[^abc]` are supported, a negated class matching the letters of the alphabet it doesn't list.
+    ///
+    /// Besides `*`, `+` and `?`, the bounded repetitions `{n}`, `{n,}` and `{n,m}` are supported.
+    ///
+    /// A backslash escapes the following character, e.g. `\*` matches the letter `*` and `\\` the backslash.
+    pub fn from_pattern(s: &str) -> Result<Regex<char>, ParseError> {
+        let tokens = tokens(s);
+        let mut alphabet = letters(&tokens);
+        let regex = parse(&tokens, &alphabet)?;
+        alphabet.extend(regex.alphabet());
+        Ok(Regex { alphabet, regex })
+    }
+
+    /// Parses the regex pattern like [`from_pattern`](#method.from_pattern), each letter `c` being replaced by `map(c)`.
+    ///
+    /// This builds regexes over other alphabets than `char`, e.g. `parse_with("ab*", Token::from)` for some `Token` type.
+    pub fn parse_with<V, F>(s: &str, map: F) -> Result<Regex<V>, ParseError>
+    where
+        V: Eq + Hash + Display + Copy + Clone + Debug + Ord,
+        F: Fn(char) -> V,
+    {
+        let regex = Regex::from_pattern(s)?;
+        Ok(Regex {
+            alphabet: regex.alphabet.into_iter().map(&map).collect(),
+            regex: regex.regex.map(&map),
+        })
+    }
+}
+
+#[cfg(feature = "regex-syntax")]
+impl Regex<char> {
+    /// Returns the regex described by the HIR of the `regex-syntax` crate, the alphabet being the set of letters appearing in it.
+    ///
+    /// Returns an error for the constructs which can't be represented: capture groups, look-around assertions, byte classes and invalid UTF-8,
+    /// and also the classes containing more than 256 characters (e.g. `.`).
+    pub fn from_hir(hir: &regex_syntax::hir::Hir) -> Result<Regex<char>, String> {
+        let regex = hir_operations(hir)?;
+        Ok(Regex {
+            alphabet: regex.alphabet(),
+            regex,
+        })
+    }
+}
+
+#[cfg(feature = "regex-syntax")]
+fn hir_operations(hir: &regex_syntax::hir::Hir) -> Result<Operations<char>, String> {
+    use regex_syntax::hir::{Class, HirKind};
+
+    Ok(match hir.kind() {
+        HirKind::Empty => Epsilon,
+        HirKind::Literal(literal) => std::str::from_utf8(&literal.0)
+            .map_err(|_| "the literal is not valid UTF-8".to_string())?
+            .chars()
+            .fold(Epsilon, |acc, c| acc * Letter(c)),
+        HirKind::Class(Class::Unicode(class)) => {
+            let size: u32 = class
+                .ranges()
+                .iter()
+                .map(|r| r.end() as u32 - r.start() as u32 + 1)
+                .sum();
+            if size > 256 {
+                return Err(format!("the class {} contains too many characters", hir));
+            }
+            class
+                .ranges()
+                .iter()
+                .flat_map(|r| r.start()..=r.end())
+                .fold(Empty, |mut acc, c| {
+                    acc += Letter(c);
+                    acc
+                })
+        }
+        HirKind::Class(Class::Bytes(_)) => return Err("byte classes are not supported".to_string()),
+        HirKind::Look(_) => return Err("look-around assertions are not supported".to_string()),
+        HirKind::Capture(_) => return Err("capture groups are not supported".to_string()),
+        HirKind::Repetition(repetition) => Repeat(
+            Box::new(hir_operations(&repetition.sub)?),
+            repetition.min as usize,
+            repetition.max.map(|max| max as usize),
+        ),
+        HirKind::Concat(hirs) => hirs
+            .iter()
+            .try_fold(Epsilon, |acc, h| Ok::<_, String>(acc * hir_operations(h)?))?,
+        HirKind::Alternation(hirs) => hirs.iter().try_fold(Empty, |mut acc, h| {
+            acc += hir_operations(h)?;
+            Ok::<_, String>(acc)
+        })?,
+    })
+}
+
+/// Parses the regex, the alphabet being the set of letters appearing in it.
+impl FromStr for Regex<char> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Regex<char>, ParseError> {
+        Regex::from_pattern(s)
+    }
 }
 
 impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
@@ -92,8 +282,9 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
             }
         }
 
+        // every operand simplified to ∅, the neutral element of the union
         if set.is_empty() {
-            return Epsilon;
+            return Empty;
         } else if set.len() == 1 {
             return set.into_iter().next().unwrap();
         } else if set.contains(&Epsilon) && set.len() == 2 {
@@ -160,8 +351,9 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
             }
         }
 
+        // every operand simplified to 𝜀, the neutral element of the concatenation
         if vec.is_empty() {
-            Empty
+            Epsilon
         } else if vec.len() == 1 {
             vec.pop_back().unwrap()
         } else {
@@ -218,6 +410,77 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
         }
     }
 
+    /// Returns `true` if and only if the regex matches the empty word.
+    pub(crate) fn nullable(&self) -> bool {
+        match self {
+            Union(v) => v.iter().any(|x| x.nullable()),
+            Concat(v) => v.iter().all(|x| x.nullable()),
+            Repeat(_, min, Some(max)) if max < min => false,
+            Repeat(o, min, _) => *min == 0 || o.nullable(),
+            Letter(_) | Empty | Dot(_) => false,
+            Epsilon => true,
+        }
+    }
+
+    /// Returns the Brzozowski derivative of the regex by the letter `a`, with normalized unions and concatenations.
+    pub(crate) fn derivative(&self, a: &V, alphabet: &HashSet<V>) -> Operations<V> {
+        match self {
+            Union(v) => v
+                .iter()
+                .fold(Empty, |acc, x| acc + x.derivative(a, alphabet))
+                .normalize(),
+            Concat(v) => {
+                let mut rest = v.clone();
+                let first = rest.pop_front().unwrap_or(Epsilon);
+                let rest = if rest.len() == 1 {
+                    rest.pop_front().unwrap()
+                } else if rest.is_empty() {
+                    Epsilon
+                } else {
+                    Concat(rest)
+                };
+
+                let left = first.derivative(a, alphabet) * rest.clone();
+                if first.nullable() {
+                    (left + rest.derivative(a, alphabet)).normalize()
+                } else {
+                    left
+                }
+            }
+            Repeat(_, min, Some(max)) if max < min || *max == 0 => Empty,
+            Repeat(o, min, max) => {
+                let rest = match (min.saturating_sub(1), max.map(|x| x - 1)) {
+                    (0, Some(0)) => Epsilon,
+                    (1, Some(1)) => (**o).clone(),
+                    (min, max) => Repeat(o.clone(), min, max),
+                };
+                o.derivative(a, alphabet) * rest
+            }
+            Letter(b) if a == b => Epsilon,
+            Dot(e) if alphabet.contains(a) && e.as_ref() != Some(a) => Epsilon,
+            Letter(_) | Dot(_) | Epsilon | Empty => Empty,
+        }
+    }
+
+    // the union of the letters of the alphabet other than `e`
+    fn expand_dot(e: &V, alphabet: &HashSet<V>) -> Operations<V> {
+        let mut letters: Vec<&V> = alphabet.iter().filter(|x| *x != e).collect();
+        letters.sort();
+        letters
+            .into_iter()
+            .fold(Empty, |acc, x| acc + Letter(*x))
+            .normalize()
+    }
+
+    // a union of a single element is that element
+    fn normalize(self) -> Operations<V> {
+        match self {
+            Union(v) if v.len() == 1 => v.into_iter().next().unwrap(),
+            x => x,
+        }
+    }
+
+    // Thompson's construction, a dot being expanded to the letters of the whole alphabet
     fn to_nfa(&self, alphabet: &HashSet<V>) -> NFA<V> {
         match self {
             Union(v) => v.iter().fold(NFA::new_empty(alphabet.clone()), |acc, x| {
@@ -238,7 +501,13 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
             Letter(a) => NFA::new_matching(alphabet.clone(), &[*a]),
             Epsilon => NFA::new_length(alphabet.clone(), 0),
             Empty => NFA::new_empty(alphabet.clone()),
-            Dot => NFA::new_length(alphabet.clone(), 1),
+            Dot(e) => {
+                let mut nfa = NFA::new_length(alphabet.clone(), 1);
+                if let Some(e) = e {
+                    nfa.transitions[0].remove(e);
+                }
+                nfa
+            }
         }
     }
 
@@ -261,6 +530,100 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
         alphabet
     }
 
+    // the same regex where each letter `v` is replaced by `f(v)`
+    pub(crate) fn map<W, F>(&self, f: &F) -> Operations<W>
+    where
+        W: Eq + Hash + Display + Copy + Clone + Debug + Ord,
+        F: Fn(V) -> W,
+    {
+        match self {
+            Union(v) => Union(v.iter().map(|x| x.map(f)).collect()),
+            Concat(v) => Concat(v.iter().map(|x| x.map(f)).collect()),
+            Repeat(o, min, max) => Repeat(Box::new(o.map(f)), *min, *max),
+            Letter(v) => Letter(f(*v)),
+            Epsilon => Epsilon,
+            Empty => Empty,
+            Dot(e) => Dot(e.map(f)),
+        }
+    }
+
+    // whether `to_string` returns a union, which needs parentheses inside a concatenation
+    fn is_printed_as_union(&self, alphabet: &HashSet<V>) -> bool {
+        match self {
+            Union(_) => true,
+            Dot(Some(e)) => matches!(Operations::expand_dot(e, alphabet), Union(_)),
+            _ => false,
+        }
+    }
+
+    // returns the number of characters and of bytes of `to_string`, without building it
+    fn printed_len(&self, alphabet: &HashSet<V>) -> (usize, usize) {
+        fn add((c1, b1): (usize, usize), (c2, b2): (usize, usize)) -> (usize, usize) {
+            (c1 + c2, b1 + b2)
+        }
+        fn paren(len: (usize, usize)) -> (usize, usize) {
+            if len.1 == 1 {
+                len
+            } else {
+                add(len, (2, 2))
+            }
+        }
+        fn digits(n: usize) -> usize {
+            n.to_string().len()
+        }
+        fn pop((c, b): (usize, usize)) -> (usize, usize) {
+            (c.saturating_sub(1), b.saturating_sub(1))
+        }
+
+        match self {
+            Union(v) => {
+                if v.contains(&Epsilon)
+                    && v.len() == alphabet.len() + 1
+                    && contains_dot(v, alphabet)
+                {
+                    return (2, 2);
+                }
+
+                // each element is followed by a '|', except the last one
+                let separated =
+                    |acc, x: &Operations<V>| add(add(acc, x.printed_len(alphabet)), (1, 1));
+                if alphabet.iter().all(|x| v.contains(&Letter(*x))) {
+                    pop(v
+                        .iter()
+                        .filter(|x| !matches!(x, Letter(_)))
+                        .fold((2, 2), separated))
+                } else {
+                    pop(v.iter().fold((0, 0), separated))
+                }
+            }
+            Concat(v) => v.iter().fold((0, 0), |acc, e| {
+                if e.is_printed_as_union(alphabet) {
+                    add(add(acc, e.printed_len(alphabet)), (2, 2))
+                } else {
+                    add(acc, e.printed_len(alphabet))
+                }
+            }),
+            Repeat(a, min, max) => {
+                let suffix = match (min, max) {
+                    (0, None) | (1, None) | (0, Some(1)) => 1,
+                    (0, Some(max)) => 3 + digits(*max),
+                    (min, Some(max)) if min == max => 2 + digits(*min),
+                    (min, Some(max)) => 3 + digits(*min) + digits(*max),
+                    (min, None) => 3 + digits(*min),
+                };
+                add(paren(a.printed_len(alphabet)), (suffix, suffix))
+            }
+            Letter(a) => {
+                let s = escape_letter(a);
+                (s.chars().count(), s.len())
+            }
+            Epsilon => (1, "𝜀".len()),
+            Empty => (1, "∅".len()),
+            Dot(None) => (1, 1),
+            Dot(Some(e)) => Operations::expand_dot(e, alphabet).printed_len(alphabet),
+        }
+    }
+
     fn to_string(&self, alphabet: &HashSet<V>) -> String {
         match self {
             Union(v) => {
@@ -294,13 +657,12 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
             Concat(v) => {
                 let mut acc = String::new();
                 for e in v {
-                    match e {
-                        Union(_) => {
-                            acc.push('(');
-                            acc.push_str(e.to_string(alphabet).as_str());
-                            acc.push(')');
-                        }
-                        _ => acc.push_str(e.to_string(alphabet).as_str()),
+                    if e.is_printed_as_union(alphabet) {
+                        acc.push('(');
+                        acc.push_str(e.to_string(alphabet).as_str());
+                        acc.push(')');
+                    } else {
+                        acc.push_str(e.to_string(alphabet).as_str());
                     }
                 }
                 acc
@@ -326,10 +688,11 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Operations<V> {
                     format!("{}{{{},}}", paren!(a.to_string(alphabet)), min)
                 }
             }
-            Letter(a) => a.to_string(),
+            Letter(a) => escape_letter(a),
             Epsilon => "𝜀".to_string(),
             Empty => "∅".to_string(),
-            Dot => ".".to_string(),
+            Dot(None) => ".".to_string(),
+            Dot(Some(e)) => Operations::expand_dot(e, alphabet).to_string(alphabet),
         }
     }
 }
@@ -435,9 +798,9 @@ impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> PartialOrd for Regex<V
     }
 }
 
-impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> ToString for Regex<V> {
-    fn to_string(&self) -> String {
-        self.regex.to_string(&self.alphabet)
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord> Display for Regex<V> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.regex.to_string(&self.alphabet))
     }
 }
 
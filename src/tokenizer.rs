@@ -0,0 +1,51 @@
+use crate::dfa::DFA;
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// A lexer built from an ordered list of rules, each rule being a [`DFA`](../dfa/struct.DFA.html) and the kind of token it recognizes.
+///
+/// At each step, the rule matching the longest prefix of the remaining input wins, ties being broken by the order of the rules.
+#[derive(Debug, Clone)]
+pub struct Tokenizer<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, K: Clone> {
+    rules: Vec<(DFA<V>, K)>,
+    position: usize,
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, K: Clone> Tokenizer<V, K> {
+    /// Returns a tokenizer using the given rules, the first ones having the highest priority.
+    pub fn new(rules: Vec<(DFA<V>, K)>) -> Tokenizer<V, K> {
+        Tokenizer { rules, position: 0 }
+    }
+
+    /// Returns the position of the internal cursor, i.e. the number of symbols already consumed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the internal cursor back to the start of the input.
+    pub fn reset(&mut self) {
+        self.position = 0;
+    }
+
+    /// Returns the kind and the length of the next token of `input` (starting at the internal cursor) and advances the cursor past it.
+    ///
+    /// Returns `None` if no rule matches a non-empty prefix of the remaining input, in which case the cursor doesn't move.
+    pub fn next(&mut self, input: &[V]) -> Option<(K, usize)> {
+        let rest = input.get(self.position..)?;
+
+        let mut best: Option<(&K, usize)> = None;
+        for (dfa, kind) in &self.rules {
+            if let Some(len) = dfa.longest_match(rest) {
+                if len > 0 && best.map_or(true, |(_, l)| len > l) {
+                    best = Some((kind, len));
+                }
+            }
+        }
+
+        let (kind, len) = best?;
+        self.position += len;
+        Some((kind.clone(), len))
+    }
+}
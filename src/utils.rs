@@ -5,7 +5,7 @@ use crate::{
     regex::{Operations, Operations::Letter},
 };
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
     fmt::{Debug, Display},
     hash::Hash,
 };
@@ -14,6 +14,54 @@ pub fn append_hashset<V: Eq + Hash>(a: &mut HashSet<V>, b: HashSet<V>) {
     a.extend(b.into_iter())
 }
 
+/// Explores breadth-first the states reachable from `start`, `step` returning the state reached by reading a letter (or `None` if there is no transition).
+/// Returns the states in the order of their numbers, `start` being `0`, and the transitions between these numbers.
+///
+/// This is the construction of the reachable part of a product automaton, whose states are the tuples of states.
+pub(crate) fn reachable_product<K, V, F>(
+    letters: &[V],
+    start: K,
+    step: F,
+) -> (Vec<K>, Vec<HashMap<V, usize>>)
+where
+    K: Eq + Hash + Copy,
+    V: Eq + Hash + Copy,
+    F: Fn(K, &V) -> Option<K>,
+{
+    let mut numbers = HashMap::new();
+    numbers.insert(start, 0);
+    let mut states = vec![start];
+    let mut transitions = vec![HashMap::new()];
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(state) = queue.pop_front() {
+        let num = numbers[&state];
+        for v in letters {
+            let next = match step(state, v) {
+                Some(next) => next,
+                None => continue,
+            };
+            let l = numbers.len();
+            let target = *numbers.entry(next).or_insert_with(|| {
+                states.push(next);
+                transitions.push(HashMap::new());
+                queue.push_back(next);
+                l
+            });
+            transitions[num].insert(*v, target);
+        }
+    }
+
+    (states, transitions)
+}
+
+/// Extends both alphabets to their union, so that a binary operation involving a complement is done over the same alphabet.
+pub fn share_alphabets<V: Eq + Hash + Copy>(a: &mut HashSet<V>, b: &mut HashSet<V>) {
+    a.extend(b.iter().copied());
+    b.extend(a.iter().copied());
+}
+
 pub fn append_shift_hashset(a: &mut HashSet<usize>, b: HashSet<usize>, l: usize) {
     a.extend(b.into_iter().map(|x| x + l))
 }
@@ -28,6 +76,22 @@ pub fn shift_fnda<V: Eq + Hash + Display + Copy + Clone + Debug + Ord>(a: &mut N
     shift_hashset(&mut a.initials, l);
     shift_hashset(&mut a.finals, l);
     shift_transitions(&mut a.transitions, l);
+    a.epsilon = a
+        .epsilon
+        .drain()
+        .map(|(s, ts)| (s + l, ts.into_iter().map(|t| t + l).collect()))
+        .collect();
+}
+
+pub fn append_shift_epsilon(
+    a: &mut HashMap<usize, Vec<usize>>,
+    b: HashMap<usize, Vec<usize>>,
+    l: usize,
+) {
+    a.extend(
+        b.into_iter()
+            .map(|(s, ts)| (s + l, ts.into_iter().map(|t| t + l).collect())),
+    );
 }
 
 pub fn shift_transitions<V: Eq + Hash>(a: &mut Vec<HashMap<V, Vec<usize>>>, l: usize) {
@@ -53,6 +117,16 @@ pub(crate) fn contains_dot<V: Eq + Hash + Display + Copy + Clone + Debug + Ord>(
     alphabet.iter().all(|x| set.contains(&Letter(*x)))
 }
 
+// the letter as printed in a regex, a backslash preceding the characters which have a meaning in a pattern
+pub(crate) fn escape_letter<V: Display>(v: &V) -> String {
+    let s = v.to_string();
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if "\\|()*+?.[]{}ε𝜀∅".contains(c) => format!("\\{}", c),
+        _ => s,
+    }
+}
+
 macro_rules! paren {
     ($x: expr) => {
         if $x.len() == 1 {
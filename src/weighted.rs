@@ -0,0 +1,200 @@
+use crate::{automaton::FromRawError, nfa::NFA};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+/// <https://en.wikipedia.org/wiki/Semiring>
+///
+/// The weights of a path are multiplied, and the weights of the different paths are added.
+pub trait Semiring: Clone {
+    /// The neutral element of `add`, which is absorbing for `mul`.
+    fn zero() -> Self;
+    /// The neutral element of `mul`.
+    fn one() -> Self;
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+/// The boolean semiring, where a word has the weight `true` if and only if it is accepted.
+impl Semiring for bool {
+    fn zero() -> Self {
+        false
+    }
+
+    fn one() -> Self {
+        true
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        *self || *other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        *self && *other
+    }
+}
+
+/// The probability semiring, where the weight of a word is the sum over its paths of the products of the weights.
+impl Semiring for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn one() -> Self {
+        1.0
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
+
+/// The tropical semiring, where the weight of a word is the cost of its cheapest accepting path, or infinity if it is rejected.
+///
+/// <https://en.wikipedia.org/wiki/Tropical_semiring>
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Tropical(pub f64);
+
+impl Semiring for Tropical {
+    fn zero() -> Self {
+        Tropical(f64::INFINITY)
+    }
+
+    fn one() -> Self {
+        Tropical(0.0)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Tropical(self.0.min(other.0))
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Tropical(self.0 + other.0)
+    }
+}
+
+/// A NFA where the initial states, the final states and the transitions carry a weight in a [`Semiring`](./trait.Semiring.html).
+///
+/// <https://en.wikipedia.org/wiki/Weighted_automaton>
+#[derive(Debug, Clone)]
+pub struct WeightedNFA<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Semiring> {
+    pub(crate) alphabet: HashSet<V>,
+    pub(crate) initials: HashMap<usize, W>,
+    pub(crate) finals: HashMap<usize, W>,
+    pub(crate) transitions: Vec<HashMap<V, Vec<(usize, W)>>>,
+}
+
+impl<V: Eq + Hash + Display + Copy + Clone + Debug + Ord, W: Semiring> WeightedNFA<V, W> {
+    /// Returns a weighted NFA without any state.
+    pub fn new(alphabet: HashSet<V>) -> WeightedNFA<V, W> {
+        WeightedNFA {
+            alphabet,
+            initials: HashMap::new(),
+            finals: HashMap::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Returns the weighted NFA with the states and the transitions of `nfa`, all the weights being `one`.
+    pub fn from_nfa(nfa: &NFA<V>) -> WeightedNFA<V, W> {
+        if !nfa.epsilon.is_empty() {
+            return WeightedNFA::from_nfa(&nfa.clone().remove_epsilon());
+        }
+        WeightedNFA {
+            alphabet: nfa.alphabet.clone(),
+            initials: nfa.initials.iter().map(|s| (*s, W::one())).collect(),
+            finals: nfa.finals.iter().map(|s| (*s, W::one())).collect(),
+            transitions: nfa
+                .transitions
+                .iter()
+                .map(|map| {
+                    map.iter()
+                        .map(|(v, ts)| (*v, ts.iter().map(|t| (*t, W::one())).collect()))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds a state without any transition and returns its number.
+    pub fn add_state(&mut self) -> usize {
+        self.transitions.push(HashMap::new());
+        self.transitions.len() - 1
+    }
+
+    /// Makes `state` initial with the given weight.
+    ///
+    /// Returns an error if `state` doesn't exist.
+    pub fn set_initial(&mut self, state: usize, weight: W) -> Result<(), FromRawError<V>> {
+        if state >= self.transitions.len() {
+            return Err(FromRawError::InvalidInitial(state));
+        }
+        self.initials.insert(state, weight);
+        Ok(())
+    }
+
+    /// Makes `state` final with the given weight.
+    ///
+    /// Returns an error if `state` doesn't exist.
+    pub fn set_final(&mut self, state: usize, weight: W) -> Result<(), FromRawError<V>> {
+        if state >= self.transitions.len() {
+            return Err(FromRawError::InvalidFinal(state));
+        }
+        self.finals.insert(state, weight);
+        Ok(())
+    }
+
+    /// Adds a transition from `source` to `target` labelled by `letter`, with the given weight. The letter is added to the alphabet.
+    ///
+    /// Returns an error if `source` or `target` doesn't exist.
+    pub fn add_transition(
+        &mut self,
+        source: usize,
+        letter: V,
+        target: usize,
+        weight: W,
+    ) -> Result<(), FromRawError<V>> {
+        if source >= self.transitions.len() || target >= self.transitions.len() {
+            return Err(FromRawError::InvalidTransition(source, letter, target));
+        }
+        self.alphabet.insert(letter);
+        self.transitions[source]
+            .entry(letter)
+            .or_default()
+            .push((target, weight));
+        Ok(())
+    }
+
+    /// Returns the weight of the word: the sum over all the accepting paths of the product of the weights along the path
+    /// (those of the initial state, of the transitions, and of the final state).
+    pub fn run(&self, word: &[V]) -> W {
+        let mut actuals: HashMap<usize, W> = self.initials.clone();
+        for l in word {
+            let mut next: HashMap<usize, W> = HashMap::new();
+            for (s, w) in &actuals {
+                if let Some(targets) = self.transitions[*s].get(l) {
+                    for (t, tw) in targets {
+                        let weight = w.mul(tw);
+                        let sum = match next.get(t) {
+                            Some(acc) => acc.add(&weight),
+                            None => weight,
+                        };
+                        next.insert(*t, sum);
+                    }
+                }
+            }
+            actuals = next;
+        }
+
+        actuals
+            .iter()
+            .filter_map(|(s, w)| self.finals.get(s).map(|f| w.mul(f)))
+            .fold(W::zero(), |acc, w| acc.add(&w))
+    }
+}
@@ -1,6 +1,8 @@
+use quickcheck::{Arbitrary, Gen};
 use rand::prelude::*;
 use std::cmp::Ordering::{Equal, Greater, Less};
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 
 #[derive(Debug)]
 pub struct Generator {
@@ -22,7 +24,7 @@ pub fn new_generator(alphabet: HashSet<char>, max_depth: u8) -> Generator {
 impl Generator {
     fn random_with_rng(alphabet: &[char], rng: &mut ThreadRng) -> String {
         let alphalen = alphabet.len();
-        let n = rng.gen_range(0, alphalen + 2);
+        let n = rng.gen_range(0..alphalen + 2);
 
         match n.cmp(&alphalen) {
             Equal => ".".to_string(),
@@ -41,7 +43,7 @@ impl Generator {
         }
 
         const TOTAL: u8 = 7;
-        let choice = self.rng.gen_range(0, TOTAL);
+        let choice = self.rng.gen_range(0..TOTAL);
         self.actual_depth += 1;
         let rec1 = self.run();
 
@@ -70,3 +72,75 @@ impl Generator {
         ret
     }
 }
+
+/// A random pattern over the alphabet `{a, b}` for property-based tests, shrunk to its subpatterns.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Letter(char),
+    Dot,
+    Epsilon,
+    Group(Box<Pattern>),
+    Kleene(Box<Pattern>),
+    Plus(Box<Pattern>),
+    Question(Box<Pattern>),
+    Concat(Box<Pattern>, Box<Pattern>),
+    Union(Box<Pattern>, Box<Pattern>),
+}
+
+impl Pattern {
+    fn with_depth(g: &mut Gen, depth: u8) -> Pattern {
+        let leaf = |g: &mut Gen| match g.choose(&[0, 1, 2, 3]).unwrap() {
+            0 => Pattern::Letter('a'),
+            1 => Pattern::Letter('b'),
+            2 => Pattern::Dot,
+            _ => Pattern::Epsilon,
+        };
+        if depth == 0 {
+            return leaf(g);
+        }
+
+        let sub = |g: &mut Gen| Box::new(Pattern::with_depth(g, depth - 1));
+        match g.choose(&[0, 1, 2, 3, 4, 5, 6]).unwrap() {
+            0 => Pattern::Group(sub(g)),
+            1 => Pattern::Kleene(sub(g)),
+            2 => Pattern::Plus(sub(g)),
+            3 => Pattern::Question(sub(g)),
+            4 => leaf(g),
+            5 => Pattern::Concat(sub(g), sub(g)),
+            _ => Pattern::Union(sub(g), sub(g)),
+        }
+    }
+}
+
+impl Arbitrary for Pattern {
+    fn arbitrary(g: &mut Gen) -> Pattern {
+        Pattern::with_depth(g, 3)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Pattern>> {
+        let children = match self {
+            Pattern::Letter(_) | Pattern::Dot | Pattern::Epsilon => vec![],
+            Pattern::Group(a) | Pattern::Kleene(a) | Pattern::Plus(a) | Pattern::Question(a) => {
+                vec![(**a).clone()]
+            }
+            Pattern::Concat(a, b) | Pattern::Union(a, b) => vec![(**a).clone(), (**b).clone()],
+        };
+        Box::new(children.into_iter())
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Pattern::Letter(c) => write!(f, "{}", c),
+            Pattern::Dot => write!(f, "."),
+            Pattern::Epsilon => write!(f, "𝜀"),
+            Pattern::Group(a) => write!(f, "({})", a),
+            Pattern::Kleene(a) => write!(f, "{}*", a),
+            Pattern::Plus(a) => write!(f, "{}+", a),
+            Pattern::Question(a) => write!(f, "{}?", a),
+            Pattern::Concat(a, b) => write!(f, "{}{}", a, b),
+            Pattern::Union(a, b) => write!(f, "{}|{}", a, b),
+        }
+    }
+}
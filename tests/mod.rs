@@ -2,12 +2,16 @@ mod generator;
 
 #[cfg(test)]
 mod tests {
-    use super::generator::new_generator;
-    use rustomaton::automaton::{Automata, Buildable};
-    use rustomaton::dfa::ToDfa;
-    use rustomaton::nfa::{ToNfa, NFA};
-    use rustomaton::regex::{Regex, ToRegex};
-    use std::collections::{HashMap, HashSet};
+    use super::generator::{new_generator, Pattern};
+    use rustomaton::automaton::{Automata, Automaton, Buildable, FromRawError};
+    use rustomaton::dfa::{Cardinality, ToDfa, DFA};
+    use rustomaton::mealy::MealyMachine;
+    use rustomaton::moore::MooreMachine;
+    use rustomaton::nfa::{SubsetKey, ToNfa, NFA};
+    use rustomaton::regex::{ParseError, Regex, ToRegex, Token};
+    use rustomaton::tokenizer::Tokenizer;
+    use rustomaton::weighted::{Tropical, WeightedNFA};
+    use std::collections::{BTreeSet, HashMap, HashSet};
     use std::iter::repeat;
 
     // empty automaton
@@ -148,6 +152,14 @@ mod tests {
         ]
     }
 
+    // the regexes of the automata 4 to 7
+    const REGEXES: [&str; 4] = [
+        "(018)*4(5+|6|7*)?3+.29?|𝜀",
+        "2|5+|6|9*|(𝜀42?78+3|2+|71+)+",
+        "(3*8*|4(1|4)*)(9+|7*)5*6|18|8*5|4|12|9+",
+        "0(8+4*3*)*|86+(3+|578)((3*|4?6?)+|(4*|86+|2)37*|54|.|5*)|.8*|(3*0*)+|2*|7*2|.3|3*5*|(50|7)1|21|4+|(30*|6|9*2*)*|1+(608*)*",
+    ];
+
     fn automaton4() -> NFA<char> {
         Regex::parse_with_alphabet(
             (b'0'..=b'9').map(char::from).collect(),
@@ -562,4 +574,2843 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_simplify_neutral_operands() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        // a union whose operands all simplify to ∅ is ∅, a concatenation whose operands all simplify to 𝜀 is 𝜀
+        for (pattern, simplified) in &[
+            ("∅a|∅b", "∅"),
+            ("(a∅)+|∅{2}", "∅"),
+            ("𝜀*𝜀*", "𝜀"),
+            ("(𝜀|𝜀)(𝜀*)", "𝜀"),
+        ] {
+            let regex = Regex::parse_with_alphabet(alphabet.clone(), pattern).unwrap();
+            let result = regex.clone().simplify();
+            assert_eq!(result.to_string(), *simplified, "{}", pattern);
+            assert!(result == regex, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_disjoint_witness() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let a = NFA::new_matching(alphabet.clone(), &['a']);
+        let b = NFA::new_matching(alphabet.clone(), &['b']);
+
+        let a_star = a.clone().kleene().to_dfa();
+        let a_star_b = a.clone().kleene().concatenate(b.clone()).to_dfa();
+        let ab_plus = a.unite(b).at_least(1).to_dfa();
+
+        assert_eq!(a_star.disjoint_witness(&a_star_b), Ok(()));
+        assert_eq!(a_star_b.disjoint_witness(&a_star), Ok(()));
+        assert_eq!(a_star_b.disjoint_witness(&ab_plus), Err(vec!['b']));
+        assert_eq!(a_star.disjoint_witness(&ab_plus), Err(vec!['a']));
+        assert_eq!(a_star.disjoint_witness(&a_star), Err(vec![]));
+    }
+
+    #[test]
+    fn test_tokenizer() {
+        let alphabet: HashSet<char> = "ab ".chars().collect();
+        let a = NFA::new_matching(alphabet.clone(), &['a']);
+        let b = NFA::new_matching(alphabet.clone(), &['b']);
+        let space = NFA::new_matching(alphabet.clone(), &[' ']);
+
+        let keyword = a.clone().concatenate(b.clone()).to_dfa();
+        let identifier = a.unite(b).at_least(1).to_dfa();
+        let blank = space.at_least(1).to_dfa();
+
+        let mut tokenizer = Tokenizer::new(vec![
+            (keyword, "keyword"),
+            (identifier, "identifier"),
+            (blank, "blank"),
+        ]);
+
+        let input: Vec<char> = "ab aba  b".chars().collect();
+        assert_eq!(tokenizer.next(&input), Some(("keyword", 2)));
+        assert_eq!(tokenizer.next(&input), Some(("blank", 1)));
+        assert_eq!(tokenizer.next(&input), Some(("identifier", 3)));
+        assert_eq!(tokenizer.next(&input), Some(("blank", 2)));
+        assert_eq!(tokenizer.next(&input), Some(("identifier", 1)));
+        assert_eq!(tokenizer.position(), input.len());
+        assert_eq!(tokenizer.next(&input), None);
+
+        tokenizer.reset();
+        assert_eq!(tokenizer.next(&['c']), None);
+        assert_eq!(tokenizer.position(), 0);
+    }
+
+    // parses `pattern`, converts it to a NFA and back to a regex, then parses the displayed regex again
+    // and checks that the language is the same at every step
+    fn assert_roundtrip(alphabet: &HashSet<char>, pattern: &str) {
+        let regex = Regex::parse_with_alphabet(alphabet.clone(), pattern)
+            .unwrap_or_else(|e| panic!("{} should parse: {}", pattern, e));
+
+        assert!(
+            regex == regex.clone().simplify(),
+            "{} : simplify changed the language",
+            pattern
+        );
+
+        let nfa = regex.to_nfa();
+        assert!(regex == nfa, "{} : to_nfa changed the language", pattern);
+
+        let back = nfa.to_regex();
+        assert!(nfa == back, "{} : to_regex changed the language", pattern);
+
+        let simplified = back.clone().simplify();
+        assert!(back == simplified, "{} : simplify changed the language", pattern);
+
+        let displayed = simplified.to_string();
+        let reparsed = Regex::parse_with_alphabet(alphabet.clone(), &displayed)
+            .unwrap_or_else(|e| panic!("{} : {} should parse: {}", pattern, displayed, e));
+        assert!(
+            reparsed == simplified,
+            "{} : reparsing {} changed the language",
+            pattern,
+            displayed
+        );
+        assert!(reparsed == regex, "{} : the roundtrip changed the language", pattern);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let alphabet: HashSet<char> = "abc".chars().collect();
+        let list = [
+            "",
+            "𝜀",
+            "∅",
+            "a",
+            ".",
+            "a*b",
+            "(a|b)*c",
+            "a+|b?c",
+            "(ab|a)*",
+            "((a|𝜀)b)+c?",
+            ".*a.",
+            "a**|(b?)+",
+            "𝜀*𝜀*",
+            "∅|∅a",
+            "(∅a)*b",
+        ];
+
+        for e in &list {
+            assert_roundtrip(&alphabet, e);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_quickcheck() {
+        fn roundtrip(pattern: Pattern) -> bool {
+            assert_roundtrip(&"ab".chars().collect(), &pattern.to_string());
+            true
+        }
+        quickcheck::QuickCheck::new()
+            .tests(100)
+            .quickcheck(roundtrip as fn(Pattern) -> bool);
+    }
+
+    #[test]
+    fn test_roundtrip_escaped() {
+        let regex: Regex<char> = r"a\*|\(b\)\.\\|[\[\]]\{c\}\𝜀\∅".parse().unwrap();
+        let displayed = regex.to_string();
+        assert!(displayed.contains(r"\*"));
+        let reparsed: Regex<char> = displayed.parse().unwrap();
+        assert!(reparsed == regex);
+        assert!(reparsed.to_dfa().run(&['a', '*']));
+        assert!(!reparsed.to_dfa().run(&['a']));
+
+        let alphabet: HashSet<char> = "a*()|".chars().collect();
+        for e in &[r"a\*", r"\(\|a\)*", r"(a|\*)+\|"] {
+            assert_roundtrip(&alphabet, e);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_random() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let mut gen = new_generator(alphabet.clone(), 3);
+        for _ in 0..100 {
+            assert_roundtrip(&alphabet, &gen.run());
+        }
+    }
+
+    #[test]
+    fn test_degrees() {
+        assert_eq!(automaton0().degrees(), vec![]);
+        assert_eq!(automaton1().degrees(), vec![(10, 10)]);
+        assert_eq!(automaton2().degrees(), vec![(2, 2), (2, 2), (2, 2)]);
+
+        let degrees = automaton3().degrees();
+        assert_eq!(degrees[0], (2, 2));
+        assert_eq!(degrees[1], (0, 0));
+        assert_eq!(degrees[7], (4, 4));
+        assert_eq!(
+            degrees.iter().map(|x| x.0).sum::<usize>(),
+            degrees.iter().map(|x| x.1).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_excluding_substring() {
+        let alphabet: HashSet<char> = "abc".chars().collect();
+        let full = NFA::new_full(alphabet.clone());
+        for pat in &["", "a", "ab", "aab", "abab", "abcab", "aaaa"] {
+            let pat: Vec<char> = pat.chars().collect();
+            let dfa = DFA::excluding_substring(alphabet.clone(), &pat);
+            let expected = full
+                .clone()
+                .concatenate(NFA::new_matching(alphabet.clone(), &pat))
+                .concatenate(full.clone())
+                .negate();
+
+            assert!(dfa.is_complete());
+            assert!(dfa == expected, "{:?}", pat);
+        }
+
+        let dfa = DFA::excluding_substring(alphabet.clone(), &['a', 'b']);
+        assert!(dfa.run(&[]));
+        assert!(dfa.run(&['b', 'a', 'c', 'a']));
+        assert!(!dfa.run(&['c', 'a', 'b', 'c']));
+
+        assert!(DFA::excluding_substring(alphabet, &['a', 'd']).is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "the state 5 doesn't exist")]
+    fn test_distinguishing_word_missing_state() {
+        DFA::from_pattern("ab").unwrap().distinguishing_word(0, 5);
+    }
+
+    #[test]
+    fn test_distinguishing_word() {
+        // states 0, 1 and 2 count the number of 1s modulo 3, the states 3 and 4 are copies of 0 and 1
+        let alphabet: HashSet<char> = "01".chars().collect();
+        let mut transitions = vec![HashMap::new(); 5];
+        for (s, t) in [(0, 1), (1, 2), (2, 3), (3, 4), (4, 2)].iter() {
+            transitions[*s].insert('0', *s);
+            transitions[*s].insert('1', *t);
+        }
+        let dfa = DFA::from_raw(alphabet, 0, vec![0, 3].into_iter().collect(), transitions).unwrap();
+
+        assert_eq!(dfa.distinguishing_word(0, 3), None);
+        assert_eq!(dfa.distinguishing_word(1, 4), None);
+        assert_eq!(dfa.distinguishing_word(2, 2), None);
+        assert_eq!(dfa.distinguishing_word(0, 1), Some(vec![]));
+        assert_eq!(dfa.distinguishing_word(1, 2), Some(vec!['1']));
+        assert_eq!(dfa.distinguishing_word(4, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_reverse_nfa() {
+        for (i, (aut, acc, rej)) in automaton_list().into_iter().enumerate() {
+            let rev = aut.to_dfa().reverse_nfa();
+            for w in &acc {
+                let w: Vec<char> = w.iter().rev().copied().collect();
+                assert!(rev.run(&w), "{} reversed should accept {:?}", i, w);
+            }
+            for w in &rej {
+                let w: Vec<char> = w.iter().rev().copied().collect();
+                assert!(!rev.run(&w), "{} reversed shouldn't accept {:?}", i, w);
+            }
+            assert!(rev == aut.reverse(), "{}", i);
+        }
+    }
+
+    #[test]
+    fn test_from_examples() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let accept = vec![vec![], vec!['a', 'b'], vec!['a', 'b', 'b'], vec!['b']];
+        let reject = vec![vec!['a'], vec!['b', 'b']];
+
+        let words = DFA::from_words(alphabet.clone(), &accept);
+        assert!(accept.iter().all(|w| words.run(w)));
+        assert!(reject.iter().all(|w| !words.run(w)));
+        assert_eq!(DFA::from_words(HashSet::new(), &accept), words);
+
+        let dfa = DFA::from_examples(alphabet.clone(), &accept, &reject).unwrap();
+        assert!(dfa.is_complete());
+        assert!(accept.iter().all(|w| dfa.run(w)));
+        assert!(reject.iter().all(|w| !dfa.run(w)));
+        assert!(!dfa.run(&['a', 'a']));
+
+        assert!(DFA::from_examples(alphabet.clone(), &accept, &[vec!['b']]).is_err());
+        assert!(DFA::from_examples(alphabet, &[vec!['c']], &reject).is_err());
+    }
+
+    #[test]
+    fn test_topo_order() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+
+        // the states of a prefix tree are numbered along the words
+        let chain = DFA::from_words(alphabet.clone(), &[vec!['a', 'b', 'a']]).complete();
+        assert_eq!(chain.topo_order(), Some(vec![0, 1, 2, 3]));
+
+        let words = vec![vec!['a'], vec!['a', 'b', 'a'], vec!['b', 'b']];
+        let order = DFA::from_words(alphabet.clone(), &words)
+            .complete()
+            .topo_order()
+            .unwrap();
+        // the sink state added by complete isn't coreachable
+        assert_eq!(order.len(), 6);
+        // the order doesn't depend on the iteration order of the hash maps
+        assert_eq!(order, vec![0, 4, 5, 1, 2, 3]);
+
+        assert_eq!(NFA::new_empty(alphabet.clone()).to_dfa().topo_order(), Some(vec![]));
+        assert_eq!(NFA::new_full(alphabet).to_dfa().topo_order(), None);
+        assert_eq!(automaton2().to_dfa().topo_order(), None);
+    }
+
+    #[test]
+    fn test_partial_determinize() {
+        for (i, (aut, acc, rej)) in automaton_list().into_iter().enumerate() {
+            let len = aut.degrees().len();
+            let all: HashSet<usize> = (0..len).collect();
+            let some: HashSet<usize> = (0..len).filter(|x| x % 2 == 0).collect();
+            for states in &[HashSet::new(), some, all] {
+                let partial = aut.clone().partial_determinize(states);
+                assert!(acc.iter().all(|w| partial.run(w)), "{}", i);
+                assert!(rej.iter().all(|w| !partial.run(w)), "{}", i);
+                assert!(partial == aut, "{} with {:?}", i, states);
+            }
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_acceptance_rate() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let full = NFA::new_full(alphabet.clone()).to_dfa();
+        let empty = NFA::new_empty(alphabet.clone()).to_dfa();
+        let ends_with_a = NFA::new_full(alphabet.clone())
+            .concatenate(NFA::new_matching(alphabet, &['a']))
+            .to_dfa();
+
+        assert_eq!(full.acceptance_rate(&mut rng, 5, 100), 1.0);
+        assert_eq!(empty.acceptance_rate(&mut rng, 5, 100), 0.0);
+        assert_eq!(ends_with_a.acceptance_rate(&mut rng, 0, 100), 0.0);
+        let rate = ends_with_a.acceptance_rate(&mut rng, 6, 1000);
+        assert!(0.4 < rate && rate < 0.6, "{}", rate);
+    }
+
+    #[test]
+    fn test_derivatives() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let regex = Regex::parse_with_alphabet(alphabet.clone(), "ab*").unwrap();
+        let derivatives = regex.derivatives();
+        let prefixes: Vec<Vec<char>> = derivatives.iter().map(|x| x.0.clone()).collect();
+        assert_eq!(prefixes, vec![vec![], vec!['a'], vec!['b']]);
+        assert!(derivatives[0].1 == regex);
+        assert!(derivatives[1].1 == Regex::parse_with_alphabet(alphabet.clone(), "b*").unwrap());
+        assert!(derivatives[2].1 == NFA::new_empty(alphabet));
+
+        let digits: HashSet<char> = (b'0'..=b'9').map(char::from).collect();
+        for (i, (aut, acc, rej)) in automaton_list().into_iter().enumerate().skip(4) {
+            let dfa = Regex::parse_with_alphabet(digits.clone(), REGEXES[i - 4])
+                .unwrap()
+                .to_dfa_derivatives();
+            assert!(dfa.is_complete());
+            assert!(acc.iter().all(|w| dfa.run(w)), "{}", i);
+            assert!(rej.iter().all(|w| !dfa.run(w)), "{}", i);
+            assert!(dfa == aut, "{}", i);
+        }
+
+        for pattern in &["", "∅", "(a|b)*a(a|b)", "(ab|a)*", ".?b+", "(a?b?)*a"] {
+            let regex = Regex::parse_with_alphabet("ab".chars().collect(), pattern).unwrap();
+            assert!(regex.to_dfa_derivatives() == regex, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_common_shortest_word() {
+        let digits: HashSet<char> = (b'0'..=b'9').map(char::from).collect();
+        let multiple_of_3 = automaton_mult(3, 10, 0).to_dfa();
+        let even = Regex::parse_with_alphabet(digits.clone(), "(0|1|2|3|4|5|6|7|8|9)*(0|2|4|6|8)")
+            .unwrap()
+            .to_dfa();
+        let starts_with_1 = Regex::parse_with_alphabet(digits.clone(), "1.*")
+            .unwrap()
+            .to_dfa();
+
+        assert_eq!(even.common_shortest_word(&multiple_of_3), Some(vec!['0']));
+        assert_eq!(
+            even.common_shortest_word(&starts_with_1),
+            Some(vec!['1', '0'])
+        );
+        assert_eq!(
+            multiple_of_3
+                .clone()
+                .intersect(even.clone())
+                .common_shortest_word(&starts_with_1),
+            Some(vec!['1', '2'])
+        );
+        assert_eq!(
+            even.common_shortest_word(&NFA::new_empty(digits).to_dfa()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_run_total() {
+        let dfa = automaton_mult(3, 2, 0).to_dfa();
+        assert_eq!(dfa.run_total(&['1', '1']), Ok(true));
+        assert_eq!(dfa.run_total(&['1', '0']), Ok(false));
+        // '2' is in the alphabet even though there is no transition labeled by it
+        assert_eq!(dfa.run_total(&['1', '2']), Ok(false));
+        assert_eq!(dfa.run_total(&['1', 'a', 'b']), Err(1));
+        assert_eq!(dfa.run_total(&['2', '1', 'a']), Err(2));
+    }
+
+    #[test]
+    fn test_growth() {
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        assert_eq!(
+            NFA::new_full(alphabet.clone()).to_dfa().growth(5),
+            vec![1, 2, 4, 8, 16, 32]
+        );
+        assert_eq!(
+            NFA::new_empty(alphabet.clone()).to_dfa().growth(3),
+            vec![0, 0, 0, 0]
+        );
+        assert_eq!(
+            NFA::new_length(alphabet.clone(), 2).to_dfa().growth(3),
+            vec![0, 0, 4, 0]
+        );
+
+        // binary numbers divisible by 3, with leading zeros
+        let dfa = automaton_mult(3, 2, 0).to_dfa();
+        let growth = dfa.growth(6);
+        assert_eq!(growth[..4], [1, 1, 2, 3]);
+        for (n, c) in growth.iter().enumerate() {
+            assert_eq!(dfa.count_words(n), *c);
+        }
+    }
+
+    #[test]
+    fn test_subset_key() {
+        // a 256 bits bitmask
+        #[derive(PartialEq, Eq, Hash)]
+        struct Key([u128; 2]);
+        impl SubsetKey for Key {
+            fn empty() -> Self {
+                Key([0, 0])
+            }
+            fn insert(&mut self, state: usize) {
+                self.0[state / 128] |= 1 << (state % 128);
+            }
+        }
+
+        for (i, (aut, acc, rej)) in automaton_list().into_iter().enumerate() {
+            let mut dfas = vec![aut.to_dfa_with_key::<BTreeSet<usize>>()];
+            let len = aut.degrees().len();
+            if len <= 32 {
+                dfas.push(aut.to_dfa_with_key::<u32>());
+            }
+            if len <= 128 {
+                dfas.push(aut.to_dfa_with_key::<u128>());
+            }
+            if len <= 256 {
+                dfas.push(aut.to_dfa_with_key::<Key>());
+            }
+            for dfa in dfas {
+                assert!(acc.iter().all(|w| dfa.run(w)), "{}", i);
+                assert!(rej.iter().all(|w| !dfa.run(w)), "{}", i);
+                assert!(dfa == aut, "{}", i);
+            }
+        }
+
+        let alphabet: HashSet<char> = "ab".chars().collect();
+        let big = NFA::new_length(alphabet.clone(), 200).unite(NFA::new_length(alphabet, 3));
+        assert_eq!(big.to_dfa().growth(200)[3], 8);
+        assert!(big.to_dfa() == big.to_dfa_with_key::<Key>());
+    }
+
+    #[test]
+    fn test_canonical_up_to_relabeling() {
+        let ab = Regex::parse_with_alphabet(['a', 'b', 'c'].iter().copied().collect(), "ab*c")
+            .unwrap()
+            .to_dfa();
+        let ba = Regex::parse_with_alphabet(['a', 'b', 'c'].iter().copied().collect(), "ca*b")
+            .unwrap()
+            .to_dfa();
+        let other = Regex::parse_with_alphabet(['a', 'b', 'c'].iter().copied().collect(), "ab*a")
+            .unwrap()
+            .to_dfa();
+
+        let canonical = ab.canonical_up_to_relabeling();
+        assert_eq!(canonical, ba.canonical_up_to_relabeling());
+        assert_ne!(canonical, other.canonical_up_to_relabeling());
+        let mut degrees = canonical.to_nfa().degrees();
+        let mut minimal = ab.clone().minimize().to_nfa().degrees();
+        degrees.sort();
+        minimal.sort();
+        assert_eq!(degrees, minimal);
+        assert_eq!(canonical, canonical.canonical_up_to_relabeling());
+    }
+
+    #[test]
+    fn test_with_counts() {
+        let counts: HashMap<char, usize> = [('a', 2), ('b', 1), ('c', 0)].iter().copied().collect();
+        let nfa = NFA::with_counts(counts);
+        assert_eq!(nfa.degrees().len(), 6);
+        for word in &["aab", "aba", "baa"] {
+            assert!(nfa.run(&word.chars().collect::<Vec<_>>()));
+        }
+        for word in &["", "ab", "aabb", "aaab", "aabc", "bb"] {
+            assert!(!nfa.run(&word.chars().collect::<Vec<_>>()));
+        }
+        assert_eq!(nfa.to_dfa().count_words(3), 3);
+
+        let empty = NFA::with_counts(HashMap::<char, usize>::new());
+        assert!(empty.run(&[]));
+    }
+
+    #[test]
+    fn test_write_exports() {
+        let dfa = DFA::from_words(['a', 'b'].iter().copied().collect(), &[vec!['a', 'b']]);
+
+        let mut att = Vec::new();
+        dfa.write_att(&mut att).unwrap();
+        assert_eq!(String::from_utf8(att).unwrap(), dfa.to_att());
+        assert_eq!(dfa.to_att(), "0\t1\ta\n1\t2\tb\n2\n");
+
+        let mut table = Vec::new();
+        dfa.write_table(&mut table).unwrap();
+        assert_eq!(String::from_utf8(table).unwrap(), dfa.to_table());
+        assert_eq!(
+            dfa.to_table(),
+            "\t\ta\tb\n->\t0\t1\t-\n\t1\t-\t2\n*\t2\t-\t-\n"
+        );
+
+        let nfa = dfa.to_nfa();
+        let mut dot = Vec::new();
+        nfa.write_dot(&mut dot).unwrap();
+        assert_eq!(String::from_utf8(dot).unwrap(), nfa.to_dot());
+    }
+
+    #[test]
+    fn test_bisimilar() {
+        let a = Regex::parse_with_alphabet(['a', 'b', 'c'].iter().copied().collect(), "ab|ac")
+            .unwrap()
+            .to_nfa();
+        let b = Regex::parse_with_alphabet(['a', 'b', 'c'].iter().copied().collect(), "a(b|c)")
+            .unwrap()
+            .to_dfa()
+            .to_nfa();
+        assert_eq!(a, b);
+        assert!(a.bisimilar(&a));
+        assert!(b.bisimilar(&b.clone().to_dfa().minimize().to_nfa()));
+        assert!(!b.bisimilar(&NFA::new_empty(['a', 'b', 'c'].iter().copied().collect())));
+
+        let map = |v: char, t: Vec<usize>| -> HashMap<char, Vec<usize>> {
+            vec![(v, t)].into_iter().collect()
+        };
+        let split = NFA::from_raw(
+            ['a', 'b', 'c'].iter().copied().collect(),
+            vec![0].into_iter().collect(),
+            vec![3].into_iter().collect(),
+            vec![
+                map('a', vec![1, 2]),
+                map('b', vec![3]),
+                map('c', vec![3]),
+                HashMap::new(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(split, b);
+        assert!(!split.bisimilar(&b));
+        assert!(!b.bisimilar(&split));
+    }
+
+    #[test]
+    fn test_trim_reporting() {
+        let map = |v: &[(char, usize)]| -> HashMap<char, usize> { v.iter().copied().collect() };
+        // 0 -a-> 1 (final), 0 -b-> 2 (dead), 3 unreachable, 4 unreachable and final
+        let dfa = DFA::from_raw(
+            ['a', 'b'].iter().copied().collect(),
+            0,
+            vec![1, 4].into_iter().collect(),
+            vec![
+                map(&[('a', 1), ('b', 2)]),
+                map(&[('a', 1)]),
+                map(&[('a', 2)]),
+                map(&[('a', 4)]),
+                map(&[]),
+            ],
+        )
+        .unwrap();
+
+        let (trimmed, unreachable, dead) = dfa.clone().trim_reporting();
+        assert_eq!(unreachable, vec![3, 4]);
+        assert_eq!(dead, vec![2]);
+        assert_eq!(trimmed, dfa);
+        assert!(trimmed.is_trimmed());
+        assert_eq!(trimmed.to_nfa().degrees().len(), 2);
+
+        let empty = DFA::new_empty(&['a'].iter().copied().collect()).complete();
+        let (trimmed, unreachable, dead) = empty.trim_reporting();
+        assert!(unreachable.is_empty());
+        assert_eq!(dead, vec![1]);
+        assert!(trimmed.is_empty());
+    }
+
+    #[test]
+    fn test_empty_operands() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let empty = NFA::new_empty(alphabet.clone());
+        let epsilon = NFA::new_empty_word(alphabet.clone());
+        for (nfa, accepted, rejected) in automaton_list() {
+            assert!(nfa.clone().concatenate(empty.clone()).is_empty());
+            assert!(empty.clone().concatenate(nfa.clone()).is_empty());
+            let left = nfa.clone().unite(empty.clone());
+            let right = empty.clone().unite(nfa.clone());
+            for word in &accepted {
+                assert!(left.run(word) && right.run(word));
+            }
+            for word in &rejected {
+                assert!(!left.run(word) && !right.run(word));
+            }
+        }
+
+        assert_eq!(empty.clone().kleene(), epsilon);
+        assert_eq!(empty.clone().at_least(0), epsilon);
+        assert!(empty.clone().at_least(1).is_empty());
+        assert_eq!(empty.clone().at_most(2), epsilon);
+        assert_eq!(empty.clone().repeat(0..=3), epsilon);
+        assert!(empty.clone().repeat(2..=3).is_empty());
+        assert!(empty.clone().concatenate(empty.clone()).is_empty());
+        assert!(empty.to_dfa().concatenate(epsilon.to_dfa()).is_empty());
+    }
+
+    #[test]
+    fn test_is_symbol_live() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        let dfa = Regex::parse_with_alphabet(alphabet.clone(), "(ab)*|c∅")
+            .unwrap()
+            .to_dfa();
+        assert!(dfa.is_symbol_live(&'a'));
+        assert!(dfa.is_symbol_live(&'b'));
+        assert!(!dfa.is_symbol_live(&'c'));
+        assert!(!dfa.is_symbol_live(&'d'));
+        assert!(!dfa.clone().complete().is_symbol_live(&'c'));
+        assert!(!DFA::new_empty(&alphabet).negate().is_symbol_live(&'d'));
+        assert!(DFA::new_empty(&alphabet).negate().is_symbol_live(&'c'));
+    }
+
+    #[test]
+    fn test_run_str() {
+        for (nfa, accepted, rejected) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            for word in &accepted {
+                let s: String = word.iter().collect();
+                assert!(nfa.run_str(&s));
+                assert!(dfa.run_str(&s));
+            }
+            for word in &rejected {
+                let s: String = word.iter().collect();
+                assert!(!nfa.run_str(&s));
+                assert!(!dfa.run_str(&s));
+            }
+        }
+    }
+
+    #[test]
+    fn test_subset_graph() {
+        for (nfa, _, _) in automaton_list() {
+            let (subsets, transitions) = nfa.subset_graph(usize::MAX);
+            assert_eq!(subsets.len(), transitions.len());
+            let empty = subsets[1..].iter().any(|s| s.is_empty()) as usize;
+            assert_eq!(subsets.len(), nfa.to_dfa().to_nfa().degrees().len() + empty);
+
+            let (capped, transitions) = nfa.subset_graph(2);
+            assert!(capped.len() <= 2);
+            assert_eq!(capped[..], subsets[..capped.len()]);
+            assert!(transitions
+                .iter()
+                .all(|m| m.values().all(|t| *t < capped.len())));
+        }
+        let nfa = NFA::new_empty_word(['a'].iter().copied().collect());
+        assert!(nfa.subset_graph(0).0.is_empty());
+    }
+
+    #[test]
+    fn test_eq_regex() {
+        for pattern in &REGEXES {
+            let regex = pattern.parse::<Regex<char>>().unwrap();
+            let dfa = regex.to_dfa();
+            assert!(dfa == regex);
+            assert!(dfa.clone().minimize() == regex);
+            for other in &REGEXES {
+                let other = other.parse::<Regex<char>>().unwrap();
+                assert_eq!(dfa == other, dfa.to_nfa() == other.to_nfa());
+                assert_eq!(dfa == other, dfa == other.to_dfa());
+            }
+        }
+    }
+
+    #[test]
+    fn test_longest_word() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        let parse = |s: &str| {
+            Regex::parse_with_alphabet(alphabet.clone(), s)
+                .unwrap()
+                .to_dfa()
+        };
+        assert_eq!(
+            parse("a|bc|ab?c").longest_word(),
+            Ok(Some(vec!['a', 'b', 'c']))
+        );
+        assert_eq!(parse("𝜀").longest_word(), Ok(Some(vec![])));
+        assert_eq!(parse("∅").longest_word(), Ok(None));
+        assert!(parse("ab*").longest_word().is_err());
+        let words = vec![vec!['a'], vec!['b', 'b', 'a', 'c'], vec!['c', 'c']];
+        assert_eq!(
+            DFA::from_words(alphabet.clone(), &words).longest_word(),
+            Ok(Some(vec!['b', 'b', 'a', 'c']))
+        );
+    }
+
+    #[test]
+    fn test_from_pattern() {
+        for (pattern, (_, accepted, rejected)) in REGEXES.iter().zip(automaton_list()[4..].iter()) {
+            let regex = Regex::from_pattern(pattern).unwrap();
+            assert_eq!(regex, pattern.parse::<Regex<char>>().unwrap());
+            let dfa = DFA::from_pattern(pattern).unwrap();
+            let from_nfa = NFA::from_pattern(pattern).unwrap();
+            assert_eq!(dfa, regex);
+            assert_eq!(from_nfa, regex);
+            for word in accepted {
+                assert!(dfa.run(word) && from_nfa.run(word));
+            }
+            for word in rejected {
+                assert!(!dfa.run(word) && !from_nfa.run(word));
+            }
+        }
+        assert!(Regex::from_pattern("(a").is_err());
+        assert!(DFA::from_pattern("a|*").is_err());
+        assert!(NFA::from_pattern("a)").is_err());
+    }
+
+    #[test]
+    fn test_equivalence_partition() {
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa().complete();
+            let partition = dfa.equivalence_partition();
+            assert!(partition.iter().enumerate().all(|(s, c)| *c <= s));
+
+            let mut live = dfa.clone().trim_reporting().0.equivalence_partition();
+            live.sort();
+            live.dedup();
+            if !dfa.is_empty() {
+                assert_eq!(live.len(), dfa.clone().minimize().to_nfa().degrees().len());
+            }
+
+            for (s, c) in partition.iter().enumerate() {
+                for (t, d) in partition.iter().enumerate() {
+                    assert_eq!(c == d, dfa.distinguishing_word(s, t).is_none());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_weighted_nfa() {
+        for (nfa, accepted, rejected) in automaton_list() {
+            let weighted = WeightedNFA::<char, bool>::from_nfa(&nfa);
+            for word in &accepted {
+                assert!(weighted.run(word));
+            }
+            for word in &rejected {
+                assert!(!weighted.run(word));
+            }
+        }
+
+        // two paths for "ab": 0 -a-> 1 -b-> 2 with cost 1 + 5, and 0 -a-> 2 -b-> 2 with cost 2 + 1
+        let mut tropical = WeightedNFA::new(HashSet::new());
+        for _ in 0..3 {
+            tropical.add_state();
+        }
+        tropical.set_initial(0, Tropical(0.0)).unwrap();
+        tropical.set_final(2, Tropical(0.5)).unwrap();
+        tropical.add_transition(0, 'a', 1, Tropical(1.0)).unwrap();
+        tropical.add_transition(1, 'b', 2, Tropical(5.0)).unwrap();
+        tropical.add_transition(0, 'a', 2, Tropical(2.0)).unwrap();
+        tropical.add_transition(2, 'b', 2, Tropical(1.0)).unwrap();
+        assert_eq!(tropical.run(&['a', 'b']), Tropical(3.5));
+        assert_eq!(tropical.run(&['a']), Tropical(2.5));
+        assert_eq!(tropical.run(&['b']), Tropical(f64::INFINITY));
+
+        let mut probabilities = WeightedNFA::new(HashSet::new());
+        probabilities.add_state();
+        probabilities.set_initial(0, 1.0).unwrap();
+        probabilities.set_final(0, 0.5).unwrap();
+        probabilities.add_transition(0, 'a', 0, 0.25).unwrap();
+        probabilities.add_transition(0, 'b', 0, 0.25).unwrap();
+        assert!(matches!(
+            probabilities.set_initial(1, 1.0),
+            Err(FromRawError::InvalidInitial(1))
+        ));
+        assert!(matches!(
+            probabilities.set_final(2, 1.0),
+            Err(FromRawError::InvalidFinal(2))
+        ));
+        assert!(matches!(
+            probabilities.add_transition(0, 'c', 1, 1.0),
+            Err(FromRawError::InvalidTransition(0, 'c', 1))
+        ));
+        assert_eq!(probabilities.run(&['a', 'b']), 0.03125);
+    }
+
+    #[test]
+    fn test_dedup_initials_finals() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let united = NFA::new_empty_word(alphabet.clone())
+            .unite(NFA::new_empty_word(alphabet.clone()))
+            .unite(NFA::new_matching(alphabet.clone(), &['a', 'b']));
+        let dedup = united.clone().dedup_initials_finals();
+        assert_eq!(dedup, united);
+        assert_eq!(dedup.degrees().len(), united.degrees().len() - 1);
+
+        // 0 -a-> 1 and 0 -a-> 2, both final, then 1 -b-> 0
+        let map =
+            |v: &[(char, Vec<usize>)]| -> HashMap<char, Vec<usize>> { v.iter().cloned().collect() };
+        let nfa = NFA::from_raw(
+            alphabet.clone(),
+            vec![0].into_iter().collect(),
+            vec![1, 2].into_iter().collect(),
+            vec![map(&[('a', vec![1, 2])]), map(&[('b', vec![0])]), map(&[])],
+        )
+        .unwrap();
+        let dedup = nfa.clone().dedup_initials_finals();
+        assert_eq!(dedup, nfa);
+        assert_eq!(dedup.degrees(), vec![(1, 1), (1, 1)]);
+
+        for (nfa, accepted, rejected) in automaton_list() {
+            let dedup = nfa.clone().dedup_initials_finals();
+            assert!(dedup.degrees().len() <= nfa.degrees().len());
+            for word in &accepted {
+                assert!(dedup.run(word));
+            }
+            for word in &rejected {
+                assert!(!dedup.run(word));
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_lengths() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let nfa = NFA::new_lengths(alphabet.clone(), &[2, 4, 6, 4]);
+        assert_eq!(nfa.degrees().len(), 7);
+        let growth = nfa.to_dfa().growth(8);
+        assert_eq!(growth, vec![0, 0, 4, 0, 16, 0, 64, 0, 0]);
+
+        let united = NFA::new_length(alphabet.clone(), 2)
+            .unite(NFA::new_length(alphabet.clone(), 4))
+            .unite(NFA::new_length(alphabet.clone(), 6));
+        assert_eq!(nfa, united);
+
+        assert!(NFA::new_lengths(alphabet.clone(), &[]).is_empty());
+        assert_eq!(
+            NFA::new_lengths(alphabet.clone(), &[0]),
+            NFA::new_empty_word(alphabet)
+        );
+    }
+
+    #[cfg(feature = "regex-syntax")]
+    #[test]
+    fn test_from_hir() {
+        for pattern in &["ab*c", "(?:ab|c)+d?", "[a-c]{2,3}", "", "x{3,}|y"] {
+            let hir = regex_syntax::parse(pattern).unwrap();
+            let regex = Regex::from_hir(&hir).unwrap();
+            let expected = match *pattern {
+                "[a-c]{2,3}" => "(a|b|c)(a|b|c)(a|b|c)?",
+                "x{3,}|y" => "xxx+|y",
+                p => p,
+            };
+            let expected = expected.replace("?:", "");
+            assert_eq!(regex, expected.parse::<Regex<char>>().unwrap());
+        }
+
+        for pattern in &["(a)", "^a", ".", "\\bab"] {
+            let hir = regex_syntax::parse(pattern).unwrap();
+            assert!(Regex::from_hir(&hir).is_err());
+        }
+    }
+
+    #[test]
+    fn test_shortest_rejected() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let parse = |s: &str| {
+            Regex::parse_with_alphabet(alphabet.clone(), s)
+                .unwrap()
+                .to_dfa()
+        };
+        assert_eq!(parse("(a|b)*").shortest_rejected(), None);
+        assert_eq!(parse("a*").shortest_rejected(), Some(vec!['b']));
+        assert_eq!(parse("𝜀|a|b|(a|b)(a|b)(a|b)*").shortest_rejected(), None);
+        assert_eq!(
+            parse("𝜀|a|b|aa|ab|ba").shortest_rejected(),
+            Some(vec!['b', 'b'])
+        );
+        assert_eq!(parse("a(a|b)*").shortest_rejected(), Some(vec![]));
+
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            match dfa.shortest_rejected() {
+                Some(w) => {
+                    assert!(!dfa.run(&w));
+                    assert_eq!(
+                        dfa.clone()
+                            .negate()
+                            .common_shortest_word(&dfa.clone().negate()),
+                        Some(w)
+                    );
+                }
+                None => assert!(dfa.is_full()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let dfa = Regex::parse_with_alphabet(alphabet, "(a|b)a*")
+            .unwrap()
+            .to_dfa()
+            .minimize();
+        assert_eq!(
+            dfa.to_mermaid(),
+            "stateDiagram-v2\n    [*] --> S0\n    S0 --> S1 : a, b\n    S1 --> S1 : a\n    S1 --> [*]\n"
+        );
+        let nfa = dfa.to_nfa();
+        assert_eq!(nfa.to_mermaid(), dfa.to_mermaid());
+        let mut mermaid = Vec::new();
+        nfa.write_mermaid(&mut mermaid).unwrap();
+        assert_eq!(String::from_utf8(mermaid).unwrap(), nfa.to_mermaid());
+    }
+
+    #[test]
+    fn test_eq_fast_paths() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let parse = |s: &str| {
+            Regex::parse_with_alphabet(alphabet.clone(), s)
+                .unwrap()
+                .to_dfa()
+        };
+        let patterns = [
+            "𝜀",
+            "∅",
+            "(a|b)*",
+            "a*",
+            "a+",
+            "aa*",
+            "ab|ba",
+            "(ab)*",
+            "b(a|b)*|a(a|b)*|𝜀",
+        ];
+        for p in &patterns {
+            for q in &patterns {
+                let (a, b) = (parse(p), parse(q));
+                assert_eq!(a == b, a.to_nfa() == b.to_nfa(), "{} {}", p, q);
+            }
+        }
+        assert!(parse("a+") == parse("aa*"));
+        assert!(parse("b(a|b)*|a(a|b)*|𝜀") == parse("(a|b)*"));
+    }
+
+    #[test]
+    fn test_regex_size_estimate() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        for pattern in &[
+            "ab*c",
+            "(a|b)*",
+            "a?b+|c",
+            "𝜀|a",
+            "∅",
+            "(abc)*|b(a|c)",
+            "a|b|c|cc",
+            "(a|b|c)c",
+            "(𝜀|a|b|c)b",
+            "(a|b|c)*",
+        ] {
+            let dfa = Regex::parse_with_alphabet(alphabet.clone(), pattern)
+                .unwrap()
+                .to_dfa()
+                .minimize();
+            let regex = dfa.to_regex().simplify().to_string();
+            assert_eq!(
+                dfa.regex_size_estimate(),
+                regex.chars().count(),
+                "{}",
+                regex
+            );
+        }
+    }
+
+    #[test]
+    fn test_slice_for_words() {
+        for (nfa, accepted, rejected) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            let words: Vec<Vec<char>> = accepted.iter().chain(rejected.iter()).cloned().collect();
+            let slice = dfa.slice_for_words(&words);
+            assert!(slice.to_nfa().degrees().len() <= dfa.to_nfa().degrees().len());
+            for word in &words {
+                assert_eq!(slice.run(word), dfa.run(word));
+            }
+        }
+
+        let dfa = Regex::parse_with_alphabet(['a', 'b'].iter().copied().collect(), "a*b|ba*")
+            .unwrap()
+            .to_dfa();
+        let slice = dfa.slice_for_words(&[vec!['a', 'b']]);
+        assert_eq!(slice.to_nfa().degrees(), vec![(0, 1), (1, 1), (1, 0)]);
+        assert!(!slice.run(&['b']));
+    }
+
+    #[test]
+    fn test_parikh_image_up_to() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let dfa = Regex::parse_with_alphabet(alphabet.clone(), "(ab)*|bb")
+            .unwrap()
+            .to_dfa();
+        let image: HashSet<Vec<usize>> = vec![vec![0, 0], vec![1, 1], vec![0, 2], vec![2, 2]]
+            .into_iter()
+            .collect();
+        assert_eq!(dfa.parikh_image_up_to(5), image);
+        assert_eq!(
+            dfa.parikh_image_up_to(0),
+            vec![vec![0, 0]].into_iter().collect()
+        );
+
+        let counts: HashMap<char, usize> = [('a', 2), ('b', 1)].iter().copied().collect();
+        let image = NFA::with_counts(counts).to_dfa().parikh_image_up_to(10);
+        assert_eq!(image, vec![vec![2, 1]].into_iter().collect());
+        assert!(DFA::new_empty(&alphabet).parikh_image_up_to(3).is_empty());
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_to_petgraph() {
+        let dfa = Regex::parse_with_alphabet(['a', 'b'].iter().copied().collect(), "a(ba)*")
+            .unwrap()
+            .to_dfa()
+            .minimize();
+        let graph = dfa.to_petgraph();
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.edge_count(), 2);
+        let initials: Vec<_> = graph.node_weights().filter(|s| s.is_initial).collect();
+        assert_eq!(initials.len(), 1);
+        assert!(!initials[0].is_final);
+        assert_eq!(graph.node_weights().filter(|s| s.is_final).count(), 1);
+        let mut labels: Vec<char> = graph.edge_weights().copied().collect();
+        labels.sort();
+        assert_eq!(labels, vec!['a', 'b']);
+        assert_eq!(petgraph::algo::kosaraju_scc(&graph).len(), 1);
+    }
+
+    #[test]
+    fn test_optional_prefix_suffix() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        let parse = |s: &str| Regex::parse_with_alphabet(alphabet.clone(), s).unwrap();
+        let (x, p) = (parse("ab*"), parse("c|bc"));
+
+        let prefixed = x.to_nfa().optional_prefix(p.to_nfa());
+        assert_eq!(prefixed, parse("(c|bc)?ab*"));
+        assert_eq!(prefixed, p.to_nfa().repeat(0..=1).concatenate(x.to_nfa()));
+        assert_eq!(x.to_dfa().optional_prefix(p.to_dfa()), prefixed);
+
+        let suffixed = x.to_nfa().optional_suffix(p.to_nfa());
+        assert_eq!(suffixed, parse("ab*(c|bc)?"));
+        assert_eq!(suffixed, x.to_nfa().concatenate(p.to_nfa().repeat(0..=1)));
+        assert_eq!(x.to_dfa().optional_suffix(p.to_dfa()), suffixed);
+    }
+
+    #[test]
+    fn test_dfa_mutators() {
+        let mut dfa = DFA::from_words(['a', 'b'].iter().copied().collect(), &[vec!['a', 'b']]);
+        assert!(dfa.run(&['a', 'b']));
+
+        dfa.set_initial(1).unwrap();
+        assert!(dfa.run(&['b']));
+        assert!(!dfa.run(&['a', 'b']));
+
+        dfa.add_final(1).unwrap();
+        assert!(dfa.run(&[]));
+        dfa.remove_final(2).unwrap();
+        assert!(!dfa.run(&['b']));
+        dfa.remove_final(2).unwrap();
+
+        assert!(dfa.set_initial(3).is_err());
+        assert!(dfa.add_final(3).is_err());
+        assert!(dfa.remove_final(3).is_err());
+        assert!(dfa.run(&[]));
+    }
+
+    #[test]
+    fn test_prefix_accepts() {
+        let dfa = Regex::parse_with_alphabet(['a', 'b'].iter().copied().collect(), "(ab)*")
+            .unwrap()
+            .to_dfa();
+        assert_eq!(
+            dfa.prefix_accepts(&['a', 'b', 'a', 'b', 'b', 'a', 'b']),
+            vec![true, false, true, false, true, false, false, false]
+        );
+        assert_eq!(dfa.prefix_accepts(&[]), vec![true]);
+
+        for (nfa, accepted, rejected) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            for word in accepted.iter().chain(rejected.iter()) {
+                let accepts = dfa.prefix_accepts(word);
+                assert_eq!(accepts.len(), word.len() + 1);
+                for (i, a) in accepts.into_iter().enumerate() {
+                    assert_eq!(a, dfa.run(&word[..i]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dot_newline() {
+        let alphabet: HashSet<char> = ['a', '\n'].iter().copied().collect();
+        let parse = |s: &str| Regex::parse_with_alphabet(alphabet.clone(), s).unwrap();
+
+        let dot_all = parse(".a").to_dfa();
+        assert!(dot_all.run(&['\n', 'a']));
+        assert!(dot_all.run(&['a', 'a']));
+        assert_eq!(parse("(?s).a").to_dfa(), dot_all);
+
+        let dot = parse("(?-s).a").to_dfa();
+        assert!(!dot.run(&['\n', 'a']));
+        assert!(dot.run(&['a', 'a']));
+        assert_eq!(dot, parse("aa").to_dfa());
+
+        let mixed = parse("(?-s).(?s).");
+        assert_eq!(mixed, parse("a(a|\n)"));
+        assert_eq!(parse(&mixed.to_string()), mixed);
+        assert_eq!(
+            mixed.to_dfa().regex_size_estimate(),
+            mixed
+                .to_dfa()
+                .to_regex()
+                .simplify()
+                .to_string()
+                .chars()
+                .count()
+        );
+
+        let wide: HashSet<char> = ['a', 'b', '\n'].iter().copied().collect();
+        let regex = Regex::parse_with_alphabet(wide, "(?-s).*").unwrap();
+        assert_eq!(regex.to_string(), "(a|b)*");
+        assert!(regex.to_dfa().run(&['a', 'b']));
+        assert!(!regex.to_dfa().run(&['a', '\n']));
+    }
+
+    #[test]
+    fn test_invariant_under() {
+        let alphabet: HashSet<char> = ['a', 'b', 'A', 'B'].iter().copied().collect();
+        let parse = |s: &str| {
+            Regex::parse_with_alphabet(alphabet.clone(), s)
+                .unwrap()
+                .to_dfa()
+        };
+        let swap_case = |c: &char| {
+            if c.is_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        };
+
+        assert!(parse("(a|A)(b|B)*").invariant_under(swap_case));
+        assert!(!parse("a(b|B)*").invariant_under(swap_case));
+        assert!(parse("(ab|ba)*").invariant_under(|c: &char| match c {
+            'a' => 'b',
+            'b' => 'a',
+            c => *c,
+        }));
+        assert!(!parse("a*b").invariant_under(|c: &char| match c {
+            'a' => 'b',
+            'b' => 'a',
+            c => *c,
+        }));
+
+        let lower = parse("(a|A)B").map_alphabet(|c: &char| c.to_ascii_lowercase());
+        assert!(lower.run(&['a', 'b']));
+        assert!(!lower.run(&['A', 'B']));
+    }
+
+    #[test]
+    fn test_different_alphabets() {
+        let a: HashSet<char> = ['a'].iter().copied().collect();
+        let ab: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let a_star = Regex::parse_with_alphabet(a.clone(), "a*").unwrap();
+        let ab_star = Regex::parse_with_alphabet(ab.clone(), "(a|b)*").unwrap();
+
+        let intersection = a_star.to_dfa().intersect(ab_star.to_dfa());
+        assert!(intersection.run(&['a', 'a']));
+        assert!(!intersection.run(&['a', 'b']));
+        let complement = intersection.negate();
+        assert!(complement.run(&['b']));
+        assert!(complement.run(&['a', 'b']));
+        assert!(!complement.run(&['a']));
+
+        let intersection = a_star.to_nfa().intersect(ab_star.to_nfa());
+        assert!(intersection.run(&['a']));
+        assert!(!intersection.run(&['b']));
+        assert!(intersection.negate().run(&['b']));
+
+        let difference = ab_star.to_dfa() - a_star.to_dfa();
+        assert!(difference.run(&['b']));
+        assert!(!difference.run(&['a']));
+        let difference = ab_star.to_nfa() - a_star.to_nfa();
+        assert!(difference.run(&['a', 'b']));
+        assert!(!difference.run(&[]));
+
+        assert!(ab_star.to_nfa().contains(&a_star.to_nfa()));
+        assert!(!a_star.to_nfa().contains(&ab_star.to_nfa()));
+        assert_eq!(NFA::new_empty(a), NFA::new_empty(ab));
+    }
+
+    #[test]
+    fn test_suffix_automaton() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        for word in &["", "a", "abcbc", "aabbab", "abababba", "cccc"] {
+            let word: Vec<char> = word.chars().collect();
+            let dfa = DFA::suffix_automaton(alphabet.clone(), &word);
+            assert!(dfa.to_nfa().degrees().len() <= 2 * word.len().max(1));
+
+            let mut factors = Vec::new();
+            for i in 0..=word.len() {
+                for j in i..=word.len() {
+                    factors.push(word[i..j].to_vec());
+                }
+            }
+            assert_eq!(dfa, DFA::from_words(alphabet.clone(), &factors));
+        }
+    }
+
+    #[test]
+    fn test_automaton_algebra() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let parse = |s: &str| Regex::parse_with_alphabet(alphabet.clone(), s).unwrap();
+        let regex = Automaton::REG(parse("a(a|b)*"));
+        let dfa = Automaton::DFA(parse("(a|b)*b").to_dfa());
+        let nfa = Automaton::NFA(parse("ab").to_nfa());
+
+        assert!(regex.contains(&nfa));
+        assert!(dfa.contains(&nfa));
+        assert!(!nfa.contains(&regex));
+        assert!(!regex.contains(&dfa));
+
+        let both = regex.intersect(&dfa);
+        assert!(matches!(both, Automaton::DFA(_)));
+        assert!(both == parse("a(a|b)*b"));
+        let both = regex.intersect(&nfa);
+        assert!(matches!(both, Automaton::NFA(_)));
+        assert!(both == parse("ab"));
+        assert!(nfa.intersect(&Automaton::REG(parse("b*"))) == parse("∅"));
+    }
+
+    #[test]
+    fn test_enabled_symbols() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        let dfa = Regex::parse_with_alphabet(alphabet.clone(), "ab*|c")
+            .unwrap()
+            .to_dfa()
+            .minimize()
+            .complete();
+        let set = |v: &[char]| -> HashSet<char> { v.iter().copied().collect() };
+        let initial = 0;
+        assert_eq!(dfa.enabled_symbols(initial, false), alphabet);
+        assert_eq!(dfa.enabled_symbols(initial, true), set(&['a', 'c']));
+
+        let nfa = NFA::new_matching(alphabet.clone(), &['a', 'b']);
+        assert_eq!(nfa.enabled_symbols(0), set(&['a']));
+        assert_eq!(nfa.enabled_symbols(2), set(&[]));
+    }
+
+    #[test]
+    fn test_moore_compose() {
+        // outputs the parity of the number of 'a' read so far
+        let map = |v: &[(char, usize)]| -> HashMap<char, usize> { v.iter().copied().collect() };
+        let parity = MooreMachine::new(
+            ['a', 'b'].iter().copied().collect(),
+            0,
+            vec!['e', 'o'],
+            vec![map(&[('a', 1), ('b', 0)]), map(&[('a', 0), ('b', 1)])],
+        )
+        .unwrap();
+        assert_eq!(parity.run(&['a', 'b', 'a']), Some(vec!['o', 'o', 'e']));
+        assert_eq!(parity.run(&['c']), None);
+
+        // accepts the words over {e, o} without two consecutive 'o'
+        let dfa = Regex::parse_with_alphabet(['e', 'o'].iter().copied().collect(), "(e|oe)*o?")
+            .unwrap()
+            .to_dfa();
+        let no_oo = MooreMachine::from_dfa(&dfa);
+
+        let pipeline = parity.clone().compose(no_oo.clone());
+        for word in &["", "a", "ab", "aba", "abba", "aab", "bbab"] {
+            let word: Vec<char> = word.chars().collect();
+            let expected = no_oo.run(&parity.run(&word).unwrap());
+            assert_eq!(pipeline.run(&word), expected);
+        }
+
+        assert!(MooreMachine::new(HashSet::<char>::new(), 0, vec![0], vec![]).is_err());
+        assert!(MooreMachine::new(HashSet::new(), 0, vec![0], vec![map(&[('a', 0)])]).is_err());
+        assert!(MooreMachine::new(
+            ['a'].iter().copied().collect(),
+            0,
+            vec![0],
+            vec![map(&[('a', 1)])]
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_count_words_big() {
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            for n in 0..6 {
+                assert_eq!(
+                    dfa.count_words_big(n).to_string(),
+                    dfa.count_words(n).to_string()
+                );
+            }
+        }
+
+        let alphabet: HashSet<char> = ('a'..='z').collect();
+        let all = NFA::new_full(alphabet).to_dfa();
+        assert_eq!(
+            all.count_words_big(30),
+            num_bigint::BigUint::from(26u32).pow(30)
+        );
+    }
+
+    #[test]
+    fn test_is_minimal_of() {
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            let minimal = dfa.clone().minimize();
+            assert!(minimal.is_minimal());
+            assert!(minimal.is_minimal_of(&dfa));
+            // the dead state added to the automaton of the empty language is redundant
+            assert_eq!(
+                minimal.clone().complete().is_minimal_of(&dfa),
+                !dfa.is_empty()
+            );
+        }
+
+        let reference = DFA::from_pattern("(a|b)*a").unwrap();
+        let student = DFA::from_pattern("(b*a)+").unwrap().minimize();
+        assert!(student.is_minimal_of(&reference));
+
+        // the states reached after `a` and after `c` are equivalent
+        let redundant = DFA::from_pattern("ab|cb").unwrap();
+        assert!(!redundant.is_minimal());
+        assert!(!redundant.is_minimal_of(&redundant));
+
+        let wrong = DFA::from_pattern("(a|b)*b").unwrap().minimize();
+        assert!(!wrong.is_minimal_of(&reference));
+    }
+
+    #[test]
+    fn test_restrict_length() {
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            for n in 0..5 {
+                let restricted = dfa.restrict_length(n);
+                let digits = (b'0'..=b'9').map(char::from).collect();
+                let expected = dfa.clone().intersect(NFA::new_length(digits, n).to_dfa());
+                assert_eq!(restricted, expected);
+                assert_eq!(restricted.count_words(n), dfa.count_words(n));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let cases = [
+            ("a|b|c", Cardinality::Finite(3)),
+            ("(a|b)(a|b)?", Cardinality::Finite(6)),
+            ("", Cardinality::Finite(1)),
+            ("a*", Cardinality::Infinite),
+            ("ab*c|d", Cardinality::Infinite),
+        ];
+        for (pattern, cardinality) in &cases {
+            let dfa = DFA::from_pattern(pattern).unwrap();
+            assert_eq!(dfa.cardinality(), *cardinality);
+            assert_eq!(dfa.is_finite(), *cardinality != Cardinality::Infinite);
+        }
+
+        for (nfa, accept, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            if let Cardinality::Finite(c) = dfa.cardinality() {
+                assert!(dfa.is_finite());
+                assert!(c as usize >= accept.iter().collect::<HashSet<_>>().len());
+            } else {
+                assert!(dfa.longest_word().is_err());
+            }
+        }
+    }
+
+    // the words of length `len` over all the bytes
+    fn all_bytes_words(len: usize) -> DFA<u8> {
+        let alphabet: HashSet<u8> = (0..=255).collect();
+        let mut transitions: Vec<HashMap<u8, usize>> = (1..=len)
+            .map(|next| alphabet.iter().map(|&b| (b, next)).collect())
+            .collect();
+        transitions.push(HashMap::new());
+        DFA::from_raw(alphabet, 0, [len].iter().cloned().collect(), transitions).unwrap()
+    }
+
+    #[test]
+    fn test_cardinality_big() {
+        assert_eq!(
+            all_bytes_words(15).cardinality(),
+            Cardinality::Finite(1 << 120)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of accepted words overflows a u128")]
+    fn test_cardinality_overflow() {
+        all_bytes_words(16).cardinality();
+    }
+
+    #[test]
+    fn test_dfa_to_regex_arden() {
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        let regex = dfa.to_regex();
+        let compiled = regex.to_dfa();
+        assert_eq!(regex, dfa);
+        for word in &["b", "ab", "aaab"] {
+            assert!(compiled.run(&word.chars().collect::<Vec<_>>()));
+        }
+        for word in &["", "a", "ba", "abb"] {
+            assert!(!compiled.run(&word.chars().collect::<Vec<_>>()));
+        }
+
+        for pattern in &REGEXES {
+            let dfa = DFA::from_pattern(pattern).unwrap();
+            assert_eq!(dfa.to_regex(), dfa);
+        }
+        for (nfa, accept, reject) in automaton_list() {
+            let regex = nfa.to_dfa().to_regex().to_dfa();
+            for word in &accept {
+                assert!(regex.run(word));
+            }
+            for word in &reject {
+                assert!(!regex.run(word));
+            }
+        }
+
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        assert_eq!(DFA::new_empty(&alphabet).to_regex().to_string(), "∅");
+        assert_eq!(
+            NFA::new_full(alphabet).to_dfa().to_regex().to_string(),
+            ".*"
+        );
+    }
+
+    #[test]
+    fn test_nfa_from_str() {
+        let text = "# a or ab\ninitials: 0\n\nfinals: 1 2\n0 a 1,3\n3 b 2\n";
+        let nfa: NFA<char> = text.parse().unwrap();
+        assert!(nfa.run(&['a']));
+        assert!(nfa.run(&['a', 'b']));
+        assert!(!nfa.run(&['b']));
+        assert!(!nfa.run(&[]));
+        assert_eq!(
+            nfa.to_string().parse::<NFA<char>>().unwrap().to_string(),
+            nfa.to_string()
+        );
+
+        for (nfa, accept, reject) in automaton_list() {
+            let parsed: NFA<char> = nfa.to_string().parse().unwrap();
+            assert_eq!(parsed.to_string(), nfa.to_string());
+            for word in &accept {
+                assert!(parsed.run(word));
+            }
+            for word in &reject {
+                assert!(!parsed.run(word));
+            }
+        }
+
+        for text in &[
+            "initials: 0\ninitials: 1",
+            "finals: 0\nfinals: 0",
+            "initials: x",
+            "0 a",
+            "0 a 1 2",
+            "0 ab 1",
+            "0 a 1,",
+            "-1 a 0",
+        ] {
+            assert!(text.parse::<NFA<char>>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_epsilon_transitions() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let mut transitions = vec![HashMap::new(); 3];
+        transitions[0].insert('a', vec![1]);
+        transitions[1].insert('b', vec![2]);
+        let mut nfa = NFA::from_raw(
+            alphabet.clone(),
+            (0..=0).collect(),
+            (0..=0).collect(),
+            transitions,
+        )
+        .unwrap();
+        // (ab|a)*: after `a` or `ab`, go back to the start
+        nfa.add_epsilon(1, 0);
+        nfa.add_epsilon(2, 0);
+
+        assert_eq!(
+            nfa.epsilon_closure(&(1..=1).collect()),
+            [0, 1].iter().copied().collect()
+        );
+        for word in &["", "a", "ab", "aab", "aba", "abab", "aaa"] {
+            let word: Vec<char> = word.chars().collect();
+            assert!(nfa.run(&word));
+        }
+        for word in &["b", "bb", "abb", "ba", "aabb"] {
+            let word: Vec<char> = word.chars().collect();
+            assert!(!nfa.run(&word));
+        }
+
+        let expected = Regex::parse_with_alphabet(alphabet, "(ab|a)*").unwrap();
+        assert_eq!(nfa, expected);
+        assert_eq!(nfa.to_dfa(), expected);
+        assert_eq!(nfa.to_regex(), expected);
+        assert_eq!(nfa.clone().reverse().reverse(), expected);
+        let removed = nfa.clone().remove_epsilon();
+        assert_eq!(removed.epsilon_closure(&(1..=1).collect()).len(), 1);
+        assert_eq!(removed, expected);
+        assert!(NFA::from_pattern("(ab|a)*").unwrap().run_str("aaba"));
+
+        // the printed forms show the epsilon transitions
+        assert_eq!(
+            nfa.to_string(),
+            "initials: 0\nfinals: 0\n0 a 1\n1 b 2\n1 𝜀 0\n2 𝜀 0\n"
+        );
+        let parsed: NFA<char> = nfa.to_string().parse().unwrap();
+        assert_eq!(parsed.epsilon_from(1), &[0]);
+        assert_eq!(parsed.to_string(), nfa.to_string());
+        assert!(nfa.to_dot().contains("S_2 -> S_0 [label = \"𝜀\"]"));
+        let parsed = NFA::from_dot(&nfa.to_dot()).unwrap();
+        assert_eq!(parsed.epsilon_from(2), &[0]);
+        assert_eq!(parsed.to_string(), nfa.to_string());
+        assert!(nfa.to_mermaid().contains("S1 --> S0 : 𝜀"));
+        assert_eq!(nfa.degrees(), vec![(2, 1), (1, 2), (1, 1)]);
+        assert_eq!(removed.degrees(), vec![(0, 1), (3, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn test_minimize_hopcroft() {
+        let mut dfas: Vec<DFA<char>> = automaton_list()
+            .into_iter()
+            .map(|(nfa, _, _)| nfa.to_dfa())
+            .collect();
+        for pattern in &REGEXES {
+            dfas.push(DFA::from_pattern(pattern).unwrap());
+        }
+        for pattern in &["ab|cb", "(a|b)*a(a|b)(a|b)", "a*b*c*", ""] {
+            dfas.push(DFA::from_pattern(pattern).unwrap());
+        }
+        dfas.push(DFA::new_empty(&['a', 'b'].iter().copied().collect()));
+
+        for dfa in dfas {
+            let brzozowski = dfa.clone().minimize();
+            let hopcroft = dfa.clone().minimize_hopcroft();
+            assert_eq!(hopcroft, dfa);
+            assert_eq!(
+                hopcroft.equivalence_partition().len(),
+                brzozowski.equivalence_partition().len()
+            );
+            assert!(hopcroft.is_minimal());
+            let complete = dfa.clone().complete().minimize_hopcroft();
+            assert_eq!(
+                complete.equivalence_partition().len(),
+                brzozowski.equivalence_partition().len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_contains_with_witness() {
+        let a = DFA::from_pattern("a*").unwrap();
+        let b = DFA::from_pattern("a*b").unwrap();
+        assert_eq!(a.contains_with_witness(&b), Err(vec!['b']));
+        assert_eq!(b.contains_with_witness(&a), Err(vec![]));
+        assert_eq!(
+            NFA::from_pattern("a*")
+                .unwrap()
+                .contains_with_witness(&NFA::from_pattern("a*b").unwrap()),
+            Err(vec!['b'])
+        );
+
+        let all = DFA::from_pattern("(a|b)*").unwrap();
+        assert_eq!(all.contains_with_witness(&b), Ok(()));
+        assert_eq!(b.contains_with_witness(&all), Err(vec![]));
+        assert_eq!(
+            b.contains_with_witness(&DFA::from_pattern("a*b|ba").unwrap()),
+            Err(vec!['b', 'a'])
+        );
+
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            assert_eq!(dfa.contains_with_witness(&dfa), Ok(()));
+            for (other, _, _) in automaton_list() {
+                let other = other.to_dfa();
+                match dfa.contains_with_witness(&other) {
+                    Ok(()) => assert!(dfa.contains(&other)),
+                    Err(w) => assert!(other.run(&w) && !dfa.run(&w)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_words() {
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        let words: Vec<String> = dfa
+            .words()
+            .take(4)
+            .map(|w| w.into_iter().collect())
+            .collect();
+        assert_eq!(words, vec!["b", "ab", "aab", "aaab"]);
+
+        let dfa = DFA::from_pattern("(a|b)(a|b)?|c").unwrap();
+        let words: Vec<String> = dfa.words().map(|w| w.into_iter().collect()).collect();
+        assert_eq!(words, vec!["a", "b", "c", "aa", "ab", "ba", "bb"]);
+
+        assert_eq!(
+            DFA::from_pattern("").unwrap().words().collect::<Vec<_>>(),
+            vec![Vec::<char>::new()]
+        );
+        assert_eq!(
+            DFA::new_empty(&['a'].iter().copied().collect())
+                .words()
+                .next(),
+            None
+        );
+
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            for word in dfa.words().take(50) {
+                assert!(dfa.run(&word));
+            }
+            let by_length: usize = (0..3).map(|n| dfa.count_words(n) as usize).sum();
+            assert_eq!(dfa.words().take_while(|w| w.len() < 3).count(), by_length);
+        }
+    }
+
+    #[test]
+    fn test_shortest_word() {
+        let cases = [
+            ("a*b", Some("b")),
+            ("ba|ab|c(a|b)", Some("ab")),
+            ("(a|b)*", Some("")),
+            ("a+|b+", Some("a")),
+        ];
+        for (pattern, expected) in &cases {
+            let expected: Option<Vec<char>> = expected.map(|w| w.chars().collect());
+            assert_eq!(
+                DFA::from_pattern(pattern).unwrap().shortest_word(),
+                expected
+            );
+            assert_eq!(
+                NFA::from_pattern(pattern).unwrap().shortest_word(),
+                expected
+            );
+        }
+
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        assert_eq!(DFA::new_empty(&alphabet).shortest_word(), None);
+        assert_eq!(NFA::new_empty(alphabet.clone()).shortest_word(), None);
+        assert_eq!(NFA::new_empty_word(alphabet).shortest_word(), Some(vec![]));
+
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            assert_eq!(nfa.shortest_word(), dfa.shortest_word());
+            assert_eq!(dfa.shortest_word(), dfa.words().next());
+        }
+    }
+
+    #[test]
+    fn test_is_finite() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        assert!(NFA::new_length(alphabet.clone(), 3).to_dfa().is_finite());
+        assert!(DFA::new_empty(&alphabet).is_finite());
+        assert!(!NFA::new_full(alphabet.clone()).to_dfa().is_finite());
+        assert!(!DFA::from_pattern("a*").unwrap().is_finite());
+        // the cycle is only on a dead state
+        assert!(DFA::from_pattern("ab").unwrap().complete().is_finite());
+        // the cycle is on a state which isn't reachable
+        let dfa = DFA::from_raw(
+            alphabet,
+            0,
+            (0..=0).collect(),
+            vec![HashMap::new(), [('a', 1)].iter().copied().collect()],
+        )
+        .unwrap();
+        assert!(dfa.is_finite());
+    }
+
+    #[test]
+    fn test_language() {
+        let words =
+            |v: &[&str]| -> Vec<Vec<char>> { v.iter().map(|w| w.chars().collect()).collect() };
+        assert_eq!(
+            DFA::from_pattern("b|a(b|c)?").unwrap().language(),
+            Some(words(&["a", "ab", "ac", "b"]))
+        );
+        assert_eq!(
+            DFA::from_pattern("").unwrap().language(),
+            Some(vec![vec![]])
+        );
+        assert_eq!(
+            DFA::new_empty(&['a'].iter().copied().collect()).language(),
+            Some(vec![])
+        );
+        assert_eq!(DFA::from_pattern("ab*").unwrap().language(), None);
+
+        for (nfa, _, _) in automaton_list() {
+            let dfa = nfa.to_dfa();
+            match dfa.cardinality() {
+                Cardinality::Finite(c) => assert_eq!(dfa.language().unwrap().len() as u128, c),
+                Cardinality::Infinite => assert_eq!(dfa.language(), None),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        for (nfa, accept, reject) in automaton_list() {
+            let json = serde_json::to_string(&nfa).unwrap();
+            let parsed: NFA<char> = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.to_string(), nfa.to_string());
+
+            let dfa = nfa.to_dfa();
+            let json = serde_json::to_string(&dfa).unwrap();
+            let parsed: DFA<char> = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, dfa);
+            for word in &accept {
+                assert!(parsed.run(word));
+            }
+            for word in &reject {
+                assert!(!parsed.run(word));
+            }
+        }
+
+        let nfa = NFA::from_pattern("(ab|a)*").unwrap();
+        let parsed: NFA<char> =
+            serde_json::from_str(&serde_json::to_string(&nfa).unwrap()).unwrap();
+        assert_eq!(parsed, nfa);
+        assert!(parsed.run_str("aab"));
+
+        // the invalid states are rejected instead of panicking later
+        let dfa = r#"{"alphabet":["a"],"initial":0,"finals":[],"transitions":[{"a":0}]}"#;
+        assert!(serde_json::from_str::<DFA<char>>(dfa).is_ok());
+        for json in &[
+            r#"{"alphabet":["a"],"initial":1,"finals":[],"transitions":[{"a":0}]}"#,
+            r#"{"alphabet":["a"],"initial":0,"finals":[3],"transitions":[{"a":0}]}"#,
+            r#"{"alphabet":["a"],"initial":0,"finals":[],"transitions":[{"a":2}]}"#,
+            r#"{"alphabet":["a"],"initial":0,"finals":[],"transitions":[{"b":0}]}"#,
+        ] {
+            assert!(serde_json::from_str::<DFA<char>>(json).is_err(), "{}", json);
+        }
+        let error = serde_json::from_str::<DFA<char>>(
+            r#"{"alphabet":["a"],"initial":0,"finals":[],"transitions":[{"a":2}]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains(&FromRawError::InvalidTransition(0, 'a', 2).to_string()));
+
+        let nfa = r#"{"alphabet":["a"],"initials":[0],"finals":[1],"transitions":[{"a":[1]},{}],"epsilon":{"1":[0]}}"#;
+        assert!(serde_json::from_str::<NFA<char>>(nfa)
+            .unwrap()
+            .run_str("aa"));
+        for json in &[
+            r#"{"alphabet":["a"],"initials":[2],"finals":[1],"transitions":[{"a":[1]},{}],"epsilon":{}}"#,
+            r#"{"alphabet":["a"],"initials":[0],"finals":[1],"transitions":[{"a":[5]},{}],"epsilon":{}}"#,
+            r#"{"alphabet":["a"],"initials":[0],"finals":[1],"transitions":[{"a":[1]},{}],"epsilon":{"1":[7]}}"#,
+            r#"{"alphabet":["a"],"initials":[0],"finals":[1],"transitions":[{"a":[1]},{}],"epsilon":{"4":[0]}}"#,
+        ] {
+            assert!(serde_json::from_str::<NFA<char>>(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_nfa_from_dot() {
+        for (nfa, accept, reject) in automaton_list() {
+            let parsed = NFA::from_dot(&nfa.to_dot()).unwrap();
+            for word in &accept {
+                assert!(parsed.run(word));
+            }
+            for word in &reject {
+                assert!(!parsed.run(word));
+            }
+        }
+        for pattern in &REGEXES {
+            let nfa = NFA::from_pattern(pattern).unwrap();
+            assert_eq!(NFA::from_dot(&nfa.to_dot()).unwrap(), nfa);
+        }
+
+        let nfa =
+            NFA::from_dot("digraph {    node [shape = doublecircle]; S_1;    S_0 -> S_1 [label = \"a, b\"];    I_0 -> S_0;}").unwrap();
+        assert!(nfa.run(&['a']));
+        assert!(nfa.run(&['b']));
+        assert!(!nfa.run(&[]));
+
+        // the invisible start nodes aren't states
+        let nfa = NFA::from_dot("digraph { I_5 -> S_0; S_0 -> S_1 [label = \"a\"]; }").unwrap();
+        assert_eq!(nfa.num_states(), 2);
+        assert_eq!(nfa.initials(), &(0..=0).collect());
+
+        for text in &[
+            "graph {}",
+            "digraph { S_0 -> S_1; }",
+            "digraph { S_0 -> X_1 [label = \"a\"]; }",
+            "digraph { S_0 -> S_1 [label = \"ab\"]; }",
+            "digraph { node [shape = box]; }",
+        ] {
+            assert!(NFA::from_dot(text).is_err());
+        }
+    }
+
+    #[test]
+    fn test_to_dot_content() {
+        let nfa = NFA::from_raw(
+            ['a'].iter().copied().collect(),
+            (0..=0).collect(),
+            (1..=1).collect(),
+            vec![[('a', vec![1])].iter().cloned().collect(), HashMap::new()],
+        )
+        .unwrap();
+        let dot = nfa.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("node [shape = doublecircle]; S_1;"));
+        assert!(dot.contains("S_0 -> S_1 [label = \"a\"];"));
+        assert!(dot.contains("I_0 -> S_0;"));
+
+        let mut written = Vec::new();
+        nfa.write_dot(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), dot);
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let a = DFA::from_pattern("a*").unwrap();
+        assert!(a.clone().symmetric_difference(a.clone()).is_empty());
+
+        let b = DFA::from_pattern("a*b?").unwrap();
+        let difference = a.clone().symmetric_difference(b.clone());
+        assert!(!difference.is_empty());
+        assert_eq!(difference, DFA::from_pattern("a*b").unwrap());
+        assert_eq!(difference.shortest_word(), Some(vec!['b']));
+        assert_eq!(b.symmetric_difference(a), difference);
+
+        for (nfa1, _, _) in automaton_list() {
+            for (nfa2, _, _) in automaton_list() {
+                let (d1, d2) = (nfa1.to_dfa(), nfa2.to_dfa());
+                let expected = (d1.clone() - d2.clone()) + (d2.clone() - d1.clone());
+                assert_eq!(d1.symmetric_difference(d2), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Regex::from_pattern("a|").unwrap_err(),
+            ParseError::UnexpectedEnd
+        );
+        assert_eq!(
+            Regex::from_pattern("*a").unwrap_err(),
+            ParseError::UnexpectedQuantifier { pos: 0 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a|*").unwrap_err(),
+            ParseError::UnexpectedQuantifier { pos: 2 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a(b").unwrap_err(),
+            ParseError::UnbalancedParen { pos: 1 }
+        );
+        assert_eq!(
+            Regex::from_pattern("ab)").unwrap_err(),
+            ParseError::UnbalancedParen { pos: 2 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a(|b)").unwrap_err(),
+            ParseError::UnexpectedToken {
+                found: Token::Union,
+                pos: 2
+            }
+        );
+        // the positions are byte offsets
+        assert_eq!(
+            Regex::from_pattern("é|*").unwrap_err(),
+            ParseError::UnexpectedQuantifier { pos: 3 }
+        );
+        assert_eq!(
+            Regex::parse_with_alphabet(['a'].iter().copied().collect(), "ab").unwrap_err(),
+            ParseError::UnknownLetter('b')
+        );
+        assert_eq!(
+            "(a".parse::<Regex<char>>().unwrap_err(),
+            ParseError::UnbalancedParen { pos: 0 }
+        );
+        assert_eq!(
+            DFA::from_pattern("a|").unwrap_err().to_string(),
+            "unexpected end of regex"
+        );
+    }
+
+    #[test]
+    fn test_character_classes() {
+        let dfa = DFA::from_pattern("[a-c]").unwrap();
+        assert!(dfa.run(&['a']));
+        assert!(dfa.run(&['b']));
+        assert!(dfa.run(&['c']));
+        assert!(!dfa.run(&['d']));
+        assert!(!dfa.run(&['a', 'b']));
+
+        let regex =
+            Regex::parse_with_alphabet(['a', 'b'].iter().copied().collect(), "[^a]").unwrap();
+        let dfa = regex.to_dfa();
+        assert!(!dfa.run(&['a']));
+        assert!(dfa.run(&['b']));
+        assert!(!dfa.run(&[]));
+
+        let dfa = DFA::from_pattern("x[ab-]*").unwrap();
+        assert!(dfa.run(&['x', '-', 'a', 'b']));
+        assert!(!dfa.run(&['x', 'c']));
+        assert!(DFA::from_pattern("[]").unwrap().is_empty());
+
+        assert_eq!(
+            Regex::from_pattern("[c-a]").unwrap_err(),
+            ParseError::UnexpectedToken {
+                found: Token::Range,
+                pos: 2
+            }
+        );
+        assert_eq!(
+            Regex::from_pattern("[ab").unwrap_err(),
+            ParseError::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn test_escapes() {
+        let dfa = DFA::from_pattern(r"a\*b").unwrap();
+        assert!(dfa.run(&['a', '*', 'b']));
+        assert!(!dfa.run(&['a', 'b']));
+        assert!(!dfa.run(&['a', 'a', 'b']));
+
+        let dfa = DFA::from_pattern(r"\.").unwrap();
+        assert!(dfa.run(&['.']));
+        assert!(!dfa.run(&['.', '.']));
+
+        let dfa = Regex::parse_with_alphabet(['.', 'a'].iter().copied().collect(), r"\.")
+            .unwrap()
+            .to_dfa();
+        assert!(dfa.run(&['.']));
+        assert!(!dfa.run(&['a']));
+
+        let dfa = DFA::from_pattern(r"\\|\(|[\]]").unwrap();
+        assert!(dfa.run(&['\\']));
+        assert!(dfa.run(&['(']));
+        assert!(dfa.run(&[']']));
+        assert!(!dfa.run(&['|']));
+    }
+
+    #[test]
+    fn test_bounded_repetitions() {
+        let dfa = DFA::from_pattern("a{2,3}").unwrap();
+        assert!(!dfa.run(&['a']));
+        assert!(dfa.run(&['a', 'a']));
+        assert!(dfa.run(&['a', 'a', 'a']));
+        assert!(!dfa.run(&['a', 'a', 'a', 'a']));
+
+        let dfa = DFA::from_pattern("a{2,}").unwrap();
+        assert!(!dfa.run(&[]));
+        assert!(!dfa.run(&['a']));
+        assert!(dfa.run(&['a', 'a']));
+        assert!(dfa.run(&['a'; 10]));
+
+        let dfa = DFA::from_pattern("(ab){2}").unwrap();
+        assert!(dfa.run(&['a', 'b', 'a', 'b']));
+        assert!(!dfa.run(&['a', 'b']));
+
+        assert_eq!(
+            Regex::from_pattern("a{2,1}").unwrap_err(),
+            ParseError::InvalidRepetition { pos: 1 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a{x}").unwrap_err(),
+            ParseError::InvalidRepetition { pos: 1 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a{,2}").unwrap_err(),
+            ParseError::InvalidRepetition { pos: 1 }
+        );
+        assert_eq!(
+            Regex::from_pattern("a{2").unwrap_err(),
+            ParseError::UnexpectedEnd
+        );
+        assert_eq!(
+            Regex::from_pattern("{2}").unwrap_err(),
+            ParseError::UnexpectedQuantifier { pos: 0 }
+        );
+
+        // the characters of the bounds aren't letters of the alphabet
+        let a: HashSet<char> = ['a'].iter().copied().collect();
+        assert_eq!(
+            Regex::from_pattern("a{2,3}").unwrap().to_nfa().alphabet(),
+            &a
+        );
+        assert_eq!(DFA::from_pattern("a{2,3}").unwrap().alphabet(), &a);
+        let dfa = DFA::from_pattern("[^a]b{2}").unwrap();
+        assert!(!dfa.run(&['2', 'b', 'b']));
+        assert!(!DFA::from_pattern("a.{1}").unwrap().run(&['a', '1']));
+        assert!(Regex::parse_with("a{2}", |c| match c {
+            'a' => 0u8,
+            _ => panic!("{} isn't a letter", c),
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_run_traces() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let mut transitions = vec![HashMap::new(); 3];
+        transitions[0].insert('a', 1);
+        transitions[1].insert('b', 2);
+        transitions[2].insert('a', 1);
+        let dfa = DFA::from_raw(alphabet, 0, (2..=2).collect(), transitions).unwrap();
+        assert_eq!(
+            dfa.run_trace(&['a', 'b', 'a', 'b']),
+            (true, vec![0, 1, 2, 1, 2])
+        );
+        assert_eq!(dfa.run_trace(&['a', 'b', 'a']), (false, vec![0, 1, 2, 1]));
+        assert_eq!(dfa.run_trace(&['a', 'a', 'b']), (false, vec![0, 1]));
+
+        let nfa: NFA<char> = "initials: 0\nfinals: 2\n0 a 0,1\n1 b 2\n".parse().unwrap();
+        let set = |states: &[usize]| states.iter().copied().collect::<HashSet<usize>>();
+        assert_eq!(
+            nfa.run_trace(&['a', 'a', 'b']),
+            (true, vec![set(&[0]), set(&[0, 1]), set(&[0, 1]), set(&[2])])
+        );
+        assert_eq!(
+            nfa.run_trace(&['a', 'b', 'b', 'a']),
+            (false, vec![set(&[0]), set(&[0, 1]), set(&[2])])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_mapping() {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        enum Digit {
+            Even(u32),
+            Odd(u32),
+        }
+
+        impl std::fmt::Display for Digit {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    Digit::Even(d) | Digit::Odd(d) => write!(f, "{}", d),
+                }
+            }
+        }
+
+        let digit = |c: char| {
+            let d = c.to_digit(10).unwrap();
+            if "02468".contains(c) {
+                Digit::Even(d)
+            } else {
+                Digit::Odd(d)
+            }
+        };
+
+        let dfa = Regex::parse_with("1[0-3]*9", digit).unwrap().to_dfa();
+        assert!(dfa.run(&[Digit::Odd(1), Digit::Odd(9)]));
+        assert!(dfa.run(&[Digit::Odd(1), Digit::Even(2), Digit::Odd(3), Digit::Odd(9)]));
+        assert!(!dfa.run(&[Digit::Odd(1), Digit::Odd(3)]));
+        assert!(!dfa.run(&[Digit::Odd(9)]));
+
+        let dfa = Regex::parse_with(".5", digit).unwrap().to_dfa();
+        assert!(dfa.run(&[Digit::Odd(5), Digit::Odd(5)]));
+        assert!(!dfa.run(&[Digit::Even(4), Digit::Odd(5)]));
+    }
+
+    #[test]
+    fn test_intersect_as_product() {
+        for (a, accept_a, reject_a) in automaton_list() {
+            for (b, accept_b, reject_b) in automaton_list() {
+                let (a, b) = (a.to_dfa(), b.to_dfa());
+                let product = a.clone().intersect(b.clone());
+                let complements = a.clone().negate().unite(b.clone().negate()).negate();
+                assert!(product == complements);
+                assert!(
+                    product.equivalence_partition().len()
+                        <= complements.equivalence_partition().len()
+                );
+                for word in accept_a
+                    .iter()
+                    .chain(&reject_a)
+                    .chain(&accept_b)
+                    .chain(&reject_b)
+                {
+                    assert_eq!(product.run(word), a.run(word) && b.run(word));
+                }
+            }
+        }
+
+        // the pairs with a dead state aren't built
+        let ab = DFA::from_pattern("ab").unwrap();
+        let ac = DFA::from_pattern("ac").unwrap();
+        let complements = ab.clone().negate().unite(ac.clone().negate()).negate();
+        let product = ab.intersect(ac);
+        assert!(product.is_empty());
+        // (0, 0) and (1, 1): the pairs (2, dead) and (dead, 2) reached by `b` and `c` can't become final
+        assert_eq!(product.num_states(), 2);
+        assert_eq!(complements.num_states(), 8);
+
+        let a_star_b = DFA::from_pattern("a*b").unwrap();
+        let a_b_star = DFA::from_pattern("ab*").unwrap();
+        let complements = a_star_b
+            .clone()
+            .negate()
+            .unite(a_b_star.clone().negate())
+            .negate();
+        let product = a_star_b.intersect(a_b_star);
+        assert!(product == DFA::from_pattern("ab").unwrap());
+        assert_eq!(product.num_states(), 3);
+        assert_eq!(complements.num_states(), 7);
+    }
+
+    #[test]
+    fn test_mealy_machine() {
+        let letters: Vec<char> = (b'a'..=b'z').map(char::from).collect();
+        let rot13: HashMap<char, (usize, char)> = letters
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (*c, (0, letters[(i + 13) % 26])))
+            .collect();
+        let machine = MealyMachine::new(letters.iter().copied().collect(), 0, vec![rot13]).unwrap();
+
+        let input: Vec<char> = "hello".chars().collect();
+        let output = machine.transduce(&input).unwrap();
+        assert_eq!(output.iter().collect::<String>(), "uryyb");
+        assert_eq!(machine.transduce(&output).unwrap(), input);
+        assert_eq!(machine.transduce(&[]), Some(vec![]));
+        assert_eq!(machine.transduce(&['h', '!']), None);
+        assert!(machine.is_complete());
+
+        // outputs whether the letter differs from the previous one, the state 2 being unreachable
+        let mut transitions = vec![HashMap::new(); 4];
+        transitions[0].insert('a', (1, true));
+        transitions[0].insert('b', (3, true));
+        transitions[1].insert('a', (1, false));
+        transitions[1].insert('b', (3, true));
+        transitions[2].insert('a', (0, false));
+        transitions[3].insert('a', (1, true));
+        transitions[3].insert('b', (3, false));
+        let machine =
+            MealyMachine::new(['a', 'b'].iter().copied().collect(), 0, transitions).unwrap();
+        let word = ['a', 'a', 'b', 'a', 'b', 'b'];
+        let expected = Some(vec![true, false, true, true, true, false]);
+        assert_eq!(machine.transduce(&word), expected);
+        assert!(!machine.is_complete());
+        let machine = machine.make_reachable();
+        assert_eq!(machine.transduce(&word), expected);
+        assert!(machine.is_complete());
+
+        assert!(MealyMachine::<char, char>::new(HashSet::new(), 0, vec![]).is_err());
+        let mut transitions = vec![HashMap::new()];
+        transitions[0].insert('a', (1, 'a'));
+        assert!(MealyMachine::new(['a'].iter().copied().collect(), 0, transitions).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_nfa() {
+        let nfa: NFA<char> = "initials: 0\nfinals: 2\n0 a 1\n0 b 0\n1 b 2\n2 a 1\n"
+            .parse()
+            .unwrap();
+        assert!(nfa.is_deterministic());
+        let dfa = nfa.clone().into_dfa_unchecked();
+        assert_eq!(dfa.equivalence_partition().len(), 3);
+        assert!(dfa == nfa.to_dfa());
+        assert_eq!(dfa.run_trace(&['b', 'a', 'b']), (true, vec![0, 0, 1, 2]));
+
+        let nfa: NFA<char> = "initials: 0\nfinals: 1\n0 a 0,1\n".parse().unwrap();
+        assert!(!nfa.is_deterministic());
+        let nfa: NFA<char> = "initials: 0 1\nfinals: 1\n0 a 1\n".parse().unwrap();
+        assert!(!nfa.is_deterministic());
+        let mut nfa: NFA<char> = "initials: 0\nfinals: 1\n0 a 1\n".parse().unwrap();
+        assert!(nfa.is_deterministic());
+        nfa.add_epsilon(1, 0);
+        assert!(!nfa.is_deterministic());
+    }
+
+    #[test]
+    fn test_dfa_labels() {
+        let nfa: NFA<char> = "initials: 0 1\nfinals: 2\n0 a 0,2\n1 b 2\n2 a 1\n"
+            .parse()
+            .unwrap();
+        let (dfa, labels) = nfa.to_dfa_with_labels();
+        assert!(dfa == nfa.to_dfa());
+        assert_eq!(labels.len(), dfa.equivalence_partition().len());
+        assert_eq!(
+            labels[0],
+            [0, 1].iter().copied().collect::<BTreeSet<usize>>()
+        );
+        for (i, label) in labels.iter().enumerate() {
+            assert!(!label.is_empty());
+            assert!(labels[i + 1..].iter().all(|other| other != label));
+        }
+
+        for (nfa, _, _) in automaton_list() {
+            let (dfa, labels) = nfa.to_dfa_with_labels();
+            assert!(dfa == nfa.to_dfa());
+            assert_eq!(labels.len(), dfa.equivalence_partition().len());
+        }
+    }
+
+    #[test]
+    fn test_prefix_closure() {
+        let nfa = NFA::from_pattern("ab").unwrap().prefix_closure();
+        assert!(nfa.run(&[]));
+        assert!(nfa.run(&['a']));
+        assert!(nfa.run(&['a', 'b']));
+        assert!(!nfa.run(&['b']));
+        assert!(!nfa.run(&['a', 'b', 'a']));
+
+        let nfa = NFA::from_pattern("a*b|c").unwrap().prefix_closure();
+        assert!(nfa == NFA::from_pattern("a*b?|c?").unwrap());
+
+        let empty = NFA::from_pattern("ab")
+            .unwrap()
+            .intersect(NFA::from_pattern("ba").unwrap());
+        assert!(empty.prefix_closure().is_empty());
+    }
+
+    #[test]
+    fn test_suffix_closure() {
+        let nfa = NFA::from_pattern("ab").unwrap().suffix_closure();
+        assert!(nfa.run(&[]));
+        assert!(nfa.run(&['b']));
+        assert!(nfa.run(&['a', 'b']));
+        assert!(!nfa.run(&['a']));
+        assert!(!nfa.run(&['b', 'a', 'b']));
+
+        let nfa = NFA::from_pattern("ab*|c").unwrap().suffix_closure();
+        assert!(nfa == NFA::from_pattern("a?b*|c?").unwrap());
+
+        let empty = NFA::from_pattern("ab")
+            .unwrap()
+            .intersect(NFA::from_pattern("ba").unwrap());
+        assert!(empty.suffix_closure().is_empty());
+    }
+
+    #[test]
+    fn test_quotients() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        let epsilon = Regex::parse_with_alphabet(alphabet.clone(), "𝜀")
+            .unwrap()
+            .to_dfa();
+
+        assert!(dfa.left_quotient(&['a']) == dfa);
+        assert!(dfa.left_quotient(&['a', 'a']) == dfa);
+        assert!(dfa.left_quotient(&['b']) == epsilon);
+        assert!(dfa.left_quotient(&['b', 'a']).is_empty());
+        assert!(dfa.left_quotient(&[]) == dfa);
+
+        assert!(
+            dfa.right_quotient(&['b'])
+                == Regex::parse_with_alphabet(alphabet.clone(), "a*")
+                    .unwrap()
+                    .to_dfa()
+        );
+        assert!(
+            dfa.right_quotient(&['a', 'b'])
+                == Regex::parse_with_alphabet(alphabet, "a*").unwrap().to_dfa()
+        );
+        assert!(dfa.right_quotient(&['a']).is_empty());
+        assert!(dfa.right_quotient(&[]) == dfa);
+    }
+
+    #[test]
+    fn test_nfa_map_alphabet() {
+        let nfa = NFA::from_pattern("Ab|aC").unwrap();
+        let lower = nfa.map_alphabet(|c: &char| c.to_ascii_lowercase());
+        assert!(lower == NFA::from_pattern("ab|ac").unwrap());
+        assert!(lower.run(&['a', 'b']));
+        assert!(lower.run(&['a', 'c']));
+        assert!(!lower.run(&['A', 'b']));
+
+        // 'A' and 'a' both map to 'a', their transitions being merged
+        let nfa: NFA<char> = "initials: 0\nfinals: 1 2\n0 A 1\n0 a 2\n1 b 1\n"
+            .parse()
+            .unwrap();
+        let lower = nfa.map_alphabet(|c: &char| c.to_ascii_lowercase());
+        assert!(!lower.is_deterministic());
+        assert!(lower == NFA::from_pattern("ab*").unwrap());
+
+        let digits = NFA::from_pattern("1(2|3)*")
+            .unwrap()
+            .map_alphabet(|c: &char| c.to_digit(10).unwrap());
+        assert!(digits.run(&[1, 3, 2]));
+        assert!(!digits.run(&[2]));
+    }
+
+    #[test]
+    fn test_projection() {
+        let nfa = NFA::from_pattern("acb").unwrap().project('c');
+        assert!(nfa.run(&['a', 'b']));
+        assert!(!nfa.run(&['a', 'c', 'b']));
+        assert!(nfa == NFA::from_pattern("ab").unwrap());
+
+        let nfa = NFA::from_pattern("(ac)*|cb").unwrap().project('c');
+        assert!(nfa == NFA::from_pattern("a*|b").unwrap());
+        assert!(nfa.to_dfa().run(&['a', 'a']));
+        assert!(nfa.to_dfa().run(&[]));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let nfa: NFA<char> = "initials: 0\nfinals: 2\n0 a 1,2\n1 b 2\n2 a 0\n"
+            .parse()
+            .unwrap();
+        assert_eq!(nfa.num_states(), 3);
+        assert_eq!(nfa.alphabet(), &['a', 'b'].iter().copied().collect());
+        assert_eq!(nfa.initials(), &(0..=0).collect());
+        assert_eq!(nfa.finals(), &(2..=2).collect());
+        assert_eq!(nfa.transitions_from(0)[&'a'], vec![1, 2]);
+        assert!(nfa.epsilon_from(0).is_empty());
+
+        // counting the transitions
+        let count: usize = (0..nfa.num_states())
+            .map(|s| {
+                nfa.transitions_from(s)
+                    .values()
+                    .map(Vec::len)
+                    .sum::<usize>()
+            })
+            .sum();
+        assert_eq!(count, 4);
+
+        let dfa = DFA::from_pattern("ab*").unwrap().minimize();
+        let count: usize = (0..dfa.num_states())
+            .map(|s| dfa.transitions_from(s).len())
+            .sum();
+        assert_eq!(count, 2);
+        let after_a = dfa.transitions_from(dfa.initial())[&'a'];
+        assert!(dfa.finals().contains(&after_a));
+        assert_eq!(dfa.transitions_from(after_a)[&'b'], after_a);
+        assert_eq!(dfa.alphabet().len(), 2);
+    }
+
+    #[test]
+    fn test_nfa_builder() {
+        let mut builder = NFA::builder(['a', 'b'].iter().copied().collect());
+        let s0 = builder.add_state();
+        let s1 = builder.add_state();
+        let s2 = builder.add_state();
+        builder
+            .add_transition(s0, 'a', s1)
+            .add_transition(s1, 'b', s2)
+            .set_initial(s0)
+            .set_final(s2);
+        let nfa = builder.build().unwrap();
+        assert!(nfa.run(&['a', 'b']));
+        assert!(!nfa.run(&['a']));
+        assert!(!nfa.run(&['b', 'a']));
+        assert!(nfa == NFA::from_pattern("ab").unwrap());
+
+        let mut builder = NFA::builder(['a'].iter().copied().collect());
+        let s = builder.add_state();
+        builder.add_transition(s, 'b', s);
+        assert!(matches!(
+            builder.build(),
+            Err(FromRawError::UnknownLetter('b'))
+        ));
+
+        let mut builder = NFA::builder(['a'].iter().copied().collect());
+        let s = builder.add_state();
+        builder.add_transition(s, 'a', 1);
+        assert!(matches!(
+            builder.build(),
+            Err(FromRawError::InvalidTransition(0, 'a', 1))
+        ));
+
+        let mut builder = NFA::builder(['a'].iter().copied().collect());
+        builder.add_transition(1, 'a', 0);
+        assert!(matches!(
+            builder.build(),
+            Err(FromRawError::InvalidTransition(1, 'a', 0))
+        ));
+
+        let mut builder = NFA::<char>::builder(HashSet::new());
+        builder.set_final(0);
+        assert!(matches!(
+            builder.build(),
+            Err(FromRawError::InvalidFinal(0))
+        ));
+    }
+
+    #[test]
+    fn test_remove_state() {
+        // a(b|c), the `b` going through the state 2
+        let mut nfa: NFA<char> = "initials: 0\nfinals: 3\n0 a 1\n1 b 2\n1 c 3\n2 x 3\n"
+            .parse()
+            .unwrap();
+        assert!(nfa.run(&['a', 'b', 'x']));
+        nfa.remove_state(2);
+        assert_eq!(nfa.num_states(), 3);
+        assert!(nfa.run(&['a', 'c']));
+        assert!(!nfa.run(&['a', 'b', 'x']));
+        assert_eq!(nfa.finals(), &(2..=2).collect());
+        assert!(!nfa.transitions_from(1).contains_key(&'b'));
+
+        let mut nfa: NFA<char> = "initials: 0 1\nfinals: 2\n0 a 2\n1 b 2\n".parse().unwrap();
+        nfa.add_epsilon(0, 1);
+        assert!(nfa.run(&['b']));
+        nfa.remove_state(1);
+        assert_eq!(nfa.initials(), &(0..=0).collect());
+        assert!(nfa.epsilon_from(0).is_empty());
+        assert!(nfa == NFA::from_pattern("a").unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "the state 100 doesn't exist")]
+    fn test_remove_missing_state() {
+        NFA::from_pattern("ab").unwrap().remove_state(100);
+    }
+
+    #[test]
+    fn test_union_all() {
+        let words = ["ab", "ba", "abc"];
+        let nfas: Vec<NFA<char>> = words
+            .iter()
+            .map(|w| NFA::from_pattern(w).unwrap())
+            .collect();
+        let union = NFA::union_all(nfas.clone());
+        for word in &words {
+            assert!(union.run(&word.chars().collect::<Vec<_>>()));
+        }
+        for word in &["", "a", "b", "abab", "bac", "abcd"] {
+            assert!(!union.run(&word.chars().collect::<Vec<_>>()));
+        }
+        assert_eq!(
+            union.num_states(),
+            nfas.iter().map(NFA::num_states).sum::<usize>()
+        );
+
+        let chained = nfas.into_iter().reduce(|a, b| a.unite(b)).unwrap();
+        assert!(union == chained);
+        assert!(union == NFA::from_pattern("ab|ba|abc").unwrap());
+
+        let empty = NFA::<char>::union_all(Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(empty.num_states(), 0);
+    }
+
+    #[test]
+    fn test_dedup_transitions() {
+        let alphabet: HashSet<char> = ['a'].iter().copied().collect();
+        let mut transitions = vec![HashMap::new(); 3];
+        transitions[0].insert('a', vec![2, 1, 2]);
+        let nfa = NFA::from_raw(
+            alphabet.clone(),
+            (0..=0).collect(),
+            (1..=2).collect(),
+            transitions,
+        )
+        .unwrap();
+        assert_eq!(nfa.transitions_from(0)[&'a'], vec![2, 1, 2]);
+
+        let concatenated = nfa.clone().concatenate(nfa.clone());
+        let kleene = nfa.clone().kleene();
+        for result in &[&concatenated, &kleene] {
+            for s in 0..result.num_states() {
+                for targets in result.transitions_from(s).values() {
+                    let mut unique = targets.clone();
+                    unique.dedup();
+                    assert_eq!(&unique, targets);
+                }
+            }
+        }
+        assert!(concatenated == NFA::from_pattern("aa").unwrap());
+        assert!(kleene == NFA::from_pattern("a*").unwrap());
+
+        let mut deduped = nfa.clone();
+        deduped.dedup_transitions();
+        assert_eq!(deduped.transitions_from(0)[&'a'], vec![1, 2]);
+        assert!(deduped == nfa);
+    }
+
+    #[test]
+    fn test_canonical_dfa() {
+        let a = DFA::from_pattern("(a|b)*b").unwrap();
+        let b = NFA::from_pattern("a*b(a*b)*|b*(ab)*b").unwrap().to_dfa();
+        assert!(a == b);
+        let (ca, cb) = (a.canonical(), b.canonical());
+        assert_eq!(ca.initial(), 0);
+        assert_eq!(ca.num_states(), cb.num_states());
+        assert_eq!(ca.finals(), cb.finals());
+        for s in 0..ca.num_states() {
+            assert_eq!(ca.transitions_from(s), cb.transitions_from(s));
+        }
+
+        let mut map = HashMap::new();
+        map.insert(a, 1);
+        map.insert(b, 2);
+        map.insert(DFA::from_pattern("(a|b)*a").unwrap(), 3);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&DFA::from_pattern("(b|a)*b").unwrap()], 2);
+
+        let set: HashSet<DFA<char>> = automaton_list()
+            .into_iter()
+            .map(|(nfa, _, _)| nfa.to_dfa())
+            .collect();
+        let minimized: HashSet<DFA<char>> = automaton_list()
+            .into_iter()
+            .map(|(nfa, _, _)| nfa.to_dfa().minimize())
+            .collect();
+        assert_eq!(set.len(), minimized.len());
+        assert!(set.iter().all(|dfa| minimized.contains(dfa)));
+    }
+
+    #[test]
+    fn test_display_tables() {
+        let dfa = DFA::from_raw(
+            ['a', 'b'].iter().copied().collect(),
+            0,
+            (1..=1).collect(),
+            vec![
+                ['a'].iter().map(|v| (*v, 1)).collect(),
+                ['a', 'b'].iter().map(|v| (*v, 0)).collect(),
+            ],
+        )
+        .unwrap();
+        let table = dfa.to_string();
+        assert_eq!(table, dfa.to_table());
+        assert!(table.starts_with("\t\ta\tb\n"));
+        assert!(table.contains("->\t0\t1\t-\n"));
+        assert!(table.contains("*\t1\t0\t0\n"));
+
+        let mut nfa: NFA<char> = "initials: 0\nfinals: 1\n0 a 1,0\n1 b 1\n".parse().unwrap();
+        let mut table = Vec::new();
+        nfa.write_table(&mut table).unwrap();
+        assert_eq!(String::from_utf8(table).unwrap(), nfa.to_table());
+        assert_eq!(nfa.to_table(), "\t\ta\tb\n->\t0\t{0,1}\t-\n*\t1\t-\t{1}\n");
+        nfa.add_epsilon(1, 0);
+        assert_eq!(
+            nfa.to_table(),
+            "\t\ta\tb\t𝜀\n->\t0\t{0,1}\t-\t-\n*\t1\t-\t{1}\t{0}\n"
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sample_words() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        for _ in 0..100 {
+            let word = dfa.sample_word(&mut rng, 5).unwrap();
+            assert_eq!(word.last(), Some(&'b'));
+            assert!(word.len() <= 5);
+            assert!(dfa.run(&word));
+        }
+        assert_eq!(dfa.sample_word(&mut rng, 0), None);
+        assert_eq!(dfa.sample_word(&mut rng, 1), Some(vec!['b']));
+
+        let dfa = DFA::from_pattern("aaa(b|c)*").unwrap();
+        assert_eq!(dfa.sample_word(&mut rng, 2), None);
+        for _ in 0..100 {
+            let word = dfa.sample_word(&mut rng, 6).unwrap();
+            assert!(word.len() >= 3 && word.len() <= 6);
+            assert!(dfa.run(&word));
+        }
+        assert!(DFA::from_pattern("a")
+            .unwrap()
+            .intersect(DFA::from_pattern("b").unwrap())
+            .sample_word(&mut rng, 10)
+            .is_none());
+    }
+
+    #[test]
+    fn test_accepts_empty() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        for (nfa, expected) in [
+            (NFA::new_empty_word(alphabet.clone()), true),
+            (NFA::new_full(alphabet.clone()), true),
+            (NFA::new_length(alphabet.clone(), 2), false),
+            (NFA::new_empty(alphabet.clone()), false),
+        ] {
+            assert_eq!(nfa.accepts_empty(), expected);
+            assert_eq!(nfa.to_dfa().accepts_empty(), expected);
+            assert_eq!(nfa.run(&[]), expected);
+        }
+
+        let mut nfa: NFA<char> = "initials: 0\nfinals: 1\n0 a 1\n".parse().unwrap();
+        assert!(!nfa.accepts_empty());
+        nfa.add_epsilon(0, 1);
+        assert!(nfa.accepts_empty());
+    }
+
+    #[test]
+    fn test_nfa_complement() {
+        let alphabet: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        assert!(NFA::new_full(alphabet.clone()).complement().is_empty());
+        assert!(NFA::new_empty(alphabet.clone()).complement().is_full());
+
+        let nfa = NFA::from_pattern("a*b").unwrap();
+        let complement = nfa.complement();
+        assert!(complement.is_complete());
+        assert!(complement.run(&[]));
+        assert!(complement.run(&['b', 'a']));
+        assert!(!complement.run(&['a', 'b']));
+        assert!(complement == nfa.negate());
+    }
+
+    #[test]
+    fn test_structural_equality() {
+        let ab: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let abc: HashSet<char> = ['a', 'b', 'c'].iter().copied().collect();
+        let over_ab = Regex::parse_with_alphabet(ab, "a*b").unwrap().to_dfa();
+        let over_abc = Regex::parse_with_alphabet(abc, "a*b").unwrap().to_dfa();
+        assert!(over_ab == over_abc);
+        assert!(over_ab.contains(&over_abc) && over_abc.contains(&over_ab));
+        assert!(!over_ab.structurally_eq(&over_abc));
+
+        let other = NFA::from_pattern("b|aa*b").unwrap().to_dfa();
+        assert!(over_ab == other);
+        assert!(over_ab.structurally_eq(&other));
+        assert!(!over_ab.structurally_eq(&DFA::from_pattern("a*bb").unwrap()));
+
+        // a word with a letter outside of the alphabet is rejected
+        let full_ab = NFA::new_full(['a', 'b'].iter().copied().collect()).to_dfa();
+        let full_abc = NFA::new_full(['a', 'b', 'c'].iter().copied().collect()).to_dfa();
+        assert!(full_ab != full_abc);
+        assert!(full_abc.contains(&full_ab));
+        assert!(!full_ab.contains(&full_abc));
+    }
+
+    #[test]
+    fn test_run_iterators() {
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        let nfa = NFA::from_pattern("(a|ab)*b").unwrap();
+        assert!(dfa.run_iter("aaab".chars()));
+        assert!(!dfa.run_iter("aaba".chars()));
+        assert!(nfa.run_iter("aabab".chars()));
+        assert!(!nfa.run_iter("aabba".chars()));
+
+        let long = "a".repeat(100_000) + "b";
+        assert!(dfa.run_iter(long.chars()));
+        assert!(nfa.run_iter(long.chars()));
+
+        // the letters after getting stuck aren't consumed
+        let consumed = std::cell::Cell::new(0);
+        let counting = || {
+            "ba".chars()
+                .chain(long.chars())
+                .inspect(|_| consumed.set(consumed.get() + 1))
+        };
+        assert!(!dfa.run_iter(counting()));
+        assert_eq!(consumed.get(), 2);
+        consumed.set(0);
+        assert!(!nfa.run_iter(counting()));
+        assert_eq!(consumed.get(), 2);
+    }
+
+    #[test]
+    fn test_longest_match() {
+        let dfa = DFA::from_pattern("a+").unwrap();
+        assert_eq!(dfa.longest_match(&['a', 'a', 'a', 'b']), Some(3));
+        assert_eq!(dfa.longest_match(&['a', 'b', 'a']), Some(1));
+        assert_eq!(dfa.longest_match(&['b', 'a']), None);
+        assert_eq!(dfa.longest_match(&[]), None);
+
+        let dfa = DFA::from_pattern("(ab)*").unwrap();
+        assert_eq!(dfa.longest_match(&['a', 'b', 'a', 'b', 'a']), Some(4));
+        assert_eq!(dfa.longest_match(&['b']), Some(0));
+    }
+
+    #[test]
+    fn test_missing_transitions() {
+        let mut transitions = vec![HashMap::new(); 2];
+        transitions[0].insert('a', 1);
+        transitions[1].insert('b', 1);
+        let dfa = DFA::from_raw(
+            ['a', 'b', 'c'].iter().copied().collect(),
+            0,
+            (1..=1).collect(),
+            transitions,
+        )
+        .unwrap();
+        assert!(!dfa.is_complete());
+        assert_eq!(
+            dfa.missing_transitions(),
+            vec![(0, 'b'), (0, 'c'), (1, 'a'), (1, 'c')]
+        );
+        assert!(dfa.complete().missing_transitions().is_empty());
+    }
+
+    #[test]
+    fn test_cheapest_word() {
+        let dfa = DFA::from_pattern("aaa|bc|ccccc").unwrap();
+        let cost = |v: &char| match v {
+            'a' => 3,
+            'b' => 5,
+            _ => 1,
+        };
+        assert_eq!(dfa.cheapest_word(cost), Some((5, vec!['c'; 5])));
+        assert_eq!(dfa.cheapest_word(|_| 1), Some((2, vec!['b', 'c'])));
+        assert_eq!(
+            dfa.cheapest_word(|v| if *v == 'a' { 0 } else { 10 }),
+            Some((0, vec!['a'; 3]))
+        );
+
+        let dfa = DFA::from_pattern("(a|b)*c").unwrap();
+        assert_eq!(
+            dfa.cheapest_word(|v| if *v == 'c' { 7 } else { 1 }),
+            Some((7, vec!['c']))
+        );
+        assert_eq!(
+            DFA::from_pattern("𝜀").unwrap().cheapest_word(|_| 1),
+            Some((0, vec![]))
+        );
+        assert_eq!(DFA::from_pattern("∅").unwrap().cheapest_word(|_| 1), None);
+    }
+
+    #[test]
+    fn test_operations_over_different_alphabets() {
+        let ab: HashSet<char> = ['a', 'b'].iter().copied().collect();
+        let a_star = Regex::parse_with_alphabet(['a'].iter().copied().collect(), "a*").unwrap();
+        let ab_star = Regex::parse_with_alphabet(ab.clone(), "(a|b)*").unwrap();
+        let expected = Regex::parse_with_alphabet(ab.clone(), "a*")
+            .unwrap()
+            .to_dfa();
+
+        let dfa = a_star.to_dfa().intersect(ab_star.to_dfa());
+        assert_eq!(dfa.alphabet(), &ab);
+        assert!(dfa.structurally_eq(&expected));
+        assert!(dfa.run(&['a', 'a']));
+        assert!(!dfa.run(&['a', 'b']));
+
+        let nfa = a_star.to_nfa().intersect(ab_star.to_nfa());
+        assert_eq!(nfa.alphabet(), &ab);
+        assert!(nfa.to_dfa().structurally_eq(&expected));
+
+        let union = a_star.to_nfa().unite(NFA::from_pattern("b").unwrap());
+        assert_eq!(union.alphabet(), &ab);
+        assert!(union.run(&['b']) && union.run(&['a', 'a']) && !union.run(&['a', 'b']));
+
+        // the complement is taken over the alphabet, which can be extended
+        let mut dfa = a_star.to_dfa();
+        assert!(dfa.clone().negate().is_empty());
+        dfa.extend_alphabet(ab.iter().copied());
+        let complement = dfa.negate();
+        assert!(complement.run(&['a', 'b']));
+        assert!(!complement.run(&['a']));
+        assert!(ab_star.to_dfa() - a_star.to_dfa() == complement);
+
+        let mut nfa = a_star.to_nfa();
+        nfa.extend_alphabet(Some('b'));
+        assert!(nfa.complement() == complement);
+    }
+
+    #[test]
+    fn test_infinite_witness() {
+        let dfa = DFA::from_pattern("a*b").unwrap();
+        let (x, y, z) = dfa.infinite_witness().unwrap();
+        assert_eq!(y, vec!['a']);
+        assert!(dfa.run(&[x.clone(), z.clone()].concat()));
+        assert!(dfa.run(&[x, y.clone(), y, z].concat()));
+        assert_eq!(
+            dfa.minimize().infinite_witness(),
+            Some((vec![], vec!['a'], vec!['b']))
+        );
+
+        let dfa = DFA::from_pattern("ab(cd)*e").unwrap().minimize();
+        let (x, y, z) = dfa.infinite_witness().unwrap();
+        assert_eq!(
+            (x.clone(), y.clone(), z.clone()),
+            (vec!['a', 'b'], vec!['c', 'd'], vec!['e'])
+        );
+        for n in 0..4 {
+            let mut word = x.clone();
+            for _ in 0..n {
+                word.extend(&y);
+            }
+            word.extend(&z);
+            assert!(dfa.run(&word));
+        }
+
+        assert_eq!(DFA::from_pattern("ab|c").unwrap().infinite_witness(), None);
+        assert_eq!(DFA::from_pattern("").unwrap().infinite_witness(), None);
+    }
+
+    #[test]
+    fn test_regex_operators_to_nfa() {
+        let alphabet: HashSet<char> = ['a', 'b', 'c', '\n'].iter().copied().collect();
+        let nfa = |pattern: &str| {
+            Regex::parse_with_alphabet(alphabet.clone(), pattern)
+                .unwrap()
+                .to_nfa()
+        };
+        let check = |pattern: &str, accepted: &[&str], rejected: &[&str]| {
+            let nfa = nfa(pattern);
+            assert_eq!(nfa.alphabet(), &alphabet);
+            for w in accepted {
+                assert!(
+                    nfa.run(&w.chars().collect::<Vec<_>>()),
+                    "{} {:?}",
+                    pattern,
+                    w
+                );
+            }
+            for w in rejected {
+                assert!(
+                    !nfa.run(&w.chars().collect::<Vec<_>>()),
+                    "{} {:?}",
+                    pattern,
+                    w
+                );
+            }
+        };
+
+        check("a", &["a"], &["", "b", "aa"]);
+        check("", &[""], &["a", "\n"]);
+        check("abc", &["abc"], &["", "ab", "abcc", "acb"]);
+        check("a|bc", &["a", "bc"], &["", "b", "abc"]);
+        check("a*", &["", "a", "aaaa"], &["b", "ab"]);
+        check("(ab)+", &["ab", "abab"], &["", "a", "aba"]);
+        check("a?b", &["b", "ab"], &["", "aab"]);
+        check("a{2,3}", &["aa", "aaa"], &["a", "aaaa"]);
+        check("a{2,}", &["aa", "aaaaa"], &["", "a"]);
+        check("(a|b){2}", &["ab", "ba", "bb"], &["a", "aba"]);
+        // a dot matches every letter of the alphabet, except a newline without the `s` flag
+        check(".", &["a", "b", "c", "\n"], &["", "ab"]);
+        check("(?-s).", &["a", "b", "c"], &["", "\n", "ab"]);
+        check("(?-s)a.*c", &["ac", "abbc", "acac"], &["a", "a\nc"]);
+        check("[^a]", &["b", "c", "\n"], &["a", ""]);
+    }
 }